@@ -65,6 +65,13 @@ const scheduleResetSyncRerenders = (store: EffectStore) => {
 
 let useSignalsDepth = 0;
 let cleanUpFn: (() => void) | undefined = undefined;
+/** store started by `useHookSignals` outside of tracked render, it has no `finally` to finish it */
+let hookStore: EffectStore | undefined = undefined;
+const finishHookTracking = () => {
+  const store = hookStore;
+  hookStore = undefined;
+  store?.[EffectStoreFields.finishTracking]();
+};
 const maxSyncRerenders = 25;
 /**
  * A redux-like store whose store value is a positive 32bit integer (a 'version').
@@ -132,6 +139,8 @@ function createEffectStore(): EffectStore {
       syncRerendersCount = 0;
     },
     [EffectStoreFields.startTracking]() {
+      // render of component calling hook is over
+      finishHookTracking();
       inRender = true;
       syncRerendersCount++;
       scheduleResetSyncRerenders(this);
@@ -198,13 +207,139 @@ function createEffectStore(): EffectStore {
  */
 export function useSignals(): EffectStore {
   // console.log('useSignals')
+  const store = useEffectStore();
+  store[EffectStoreFields.startTracking]();
+  return store;
+}
+
+function useEffectStore(): EffectStore {
   const storeRef = useRef<EffectStore>();
   if (storeRef.current == null) {
     storeRef.current = createEffectStore();
   }
   const store = storeRef.current;
   useSyncExternalStore(store.subscribe, store.getSnapshot, store.getSnapshot);
+  return store;
+}
+
+/**
+ * @description `useSignals` for custom hooks, used by `@preact-signals/safe-react/swc`. It doesn't need `s.f()`:
+ * inside of tracked component signals are tracked by the component. Otherwise tracking is started for the calling
+ * component and finished by the next tracked render or in a microtask
+ * @example
+ * ```tsx
+ * const useCartTotal = () => {
+ *   useHookSignals()
+ *   return cart.value.reduce((acc, it) => acc + it, 0)
+ * }
+ * ```
+ */
+export function useHookSignals(): void {
+  // hooks are called unconditionally to keep their order stable
+  const store = useEffectStore();
+  // signals are tracked by component, tracking of another hook belongs to the
+  // previous untracked component or to the previous hook of this one
+  if (useSignalsDepth && !hookStore) {
+    return;
+  }
+  store[EffectStoreFields.startTracking]();
+  hookStore = store;
+  void _queueMicrotask(finishHookTracking);
+}
+
+interface TrackedClassComponent {
+  forceUpdate(): void;
+  componentDidMount?(): void;
+  componentWillUnmount?(): void;
+}
+interface ClassTracking {
+  store: EffectStore;
+  /** version of store, which was rendered last time */
+  renderedVersion: number;
+  /** defined only while component is mounted */
+  unsubscribe: (() => void) | undefined;
+}
+
+const classTrackings = new WeakMap<TrackedClassComponent, ClassTracking>();
+const patchedClassComponents = new WeakSet<TrackedClassComponent>();
 
+function createClassTracking(component: TrackedClassComponent): ClassTracking {
+  const tracking: ClassTracking = {
+    store: createEffectStore(),
+    renderedVersion: 0,
+    unsubscribe: undefined,
+  };
+  classTrackings.set(component, tracking);
+  return tracking;
+}
+
+/**
+ * Render of an instance can be discarded without `componentWillUnmount` (StrictMode
+ * double construction, aborted concurrent render), so instance subscribes only when mounted
+ */
+function subscribeClassComponent(
+  component: TrackedClassComponent,
+  tracking: ClassTracking
+) {
+  const { store } = tracking;
+  tracking.unsubscribe = store.subscribe(() => component.forceUpdate());
+  // signals changed between render and mount
+  if (store.getSnapshot() !== tracking.renderedVersion) {
+    component.forceUpdate();
+  }
+}
+
+function patchClassLifecycles(component: TrackedClassComponent) {
+  const { componentDidMount, componentWillUnmount } = component;
+  component.componentDidMount = function (this: TrackedClassComponent) {
+    const tracking = classTrackings.get(this);
+    if (!tracking) {
+      // StrictMode remounts the same instance after `componentWillUnmount`,
+      // signals of the last render are tracked by disposed store
+      subscribeClassComponent(this, createClassTracking(this));
+      this.forceUpdate();
+    } else if (!tracking.unsubscribe) {
+      subscribeClassComponent(this, tracking);
+    }
+    componentDidMount?.call(this);
+  };
+  component.componentWillUnmount = function (this: TrackedClassComponent) {
+    try {
+      componentWillUnmount?.call(this);
+    } finally {
+      classTrackings.get(this)?.unsubscribe?.();
+      classTrackings.delete(this);
+    }
+  };
+  patchedClassComponents.add(component);
+}
+
+/**
+ * @description hook-free version of `useSignals` for class components, used by `@preact-signals/safe-react/swc`. Signals are tracked during render, component subscribes to them in `componentDidMount`, is rerendered with `forceUpdate` and stops tracking on unmount. If s.f() is not called - reactivity will break
+ * @example
+ * ```tsx
+ * class Component extends React.Component {
+ *   render() {
+ *     const s = trackClassSignals(this)
+ *     try {
+ *       return <p>{counter.value}</p>
+ *     } finally {
+ *       s.f()
+ *     }
+ *   }
+ * }
+ * ```
+ */
+export function trackClassSignals(
+  component: TrackedClassComponent
+): EffectStore {
+  const tracking =
+    classTrackings.get(component) ?? createClassTracking(component);
+  if (!patchedClassComponents.has(component)) {
+    patchClassLifecycles(component);
+  }
+  const store = tracking.store;
+  tracking.renderedVersion = store.getSnapshot();
   store[EffectStoreFields.startTracking]();
   return store;
 }
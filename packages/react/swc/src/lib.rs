@@ -2,10 +2,12 @@
 
 mod utils;
 use swc_core::ecma::transforms::testing::test_inline;
+#[cfg(test)]
+use swc_core::ecma::transforms::{base::pass::noop, testing::Tester};
 use utils::*;
 
 use std::{
-    borrow::BorrowMut,
+    collections::{HashMap, HashSet},
     fmt::Debug,
     ops::{Deref, DerefMut},
 };
@@ -16,13 +18,13 @@ use serde_json;
 use std::path::PathBuf;
 use swc_core::{
     common::comments::Comments,
-    common::{comments::CommentKind, sync::Lazy, Span, DUMMY_SP},
+    common::{sync::Lazy, Mark, Span, DUMMY_SP},
     ecma::{
         ast::*,
         atoms::Atom,
         parser::Syntax,
         utils::{prepend_stmt, private_ident},
-        visit::{as_folder, noop_visit_mut_type, FoldWith, VisitMut, VisitMutWith},
+        visit::{as_folder, noop_visit_mut_type, FoldWith, VisitMut, VisitMutWith, VisitWith},
     },
     plugin::{
         metadata::TransformPluginMetadataContextKind, plugin_transform,
@@ -37,15 +39,33 @@ fn get_import_source(str: &str) -> Str {
         raw: None,
     }
 }
-fn is_track_signals_directive(string: &str) -> bool {
-    static RE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"\s@useSignals\s"#).unwrap());
+/// `@useSignals` anywhere in comment, optionally followed by `true` or `false`
+static USE_SIGNALS_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?:^|[\s*])@useSignals(?:\s+(true|false)\b|\s|\*|$)"#).unwrap());
 
-    RE.is_match(string)
+fn is_track_signals_directive(string: &str) -> bool {
+    USE_SIGNALS_RE
+        .captures_iter(string)
+        .any(|it| it.get(1).map_or(true, |value| value.as_str() != "false"))
 }
 fn is_no_track_signals_directive(string: &str) -> bool {
-    static RE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"\s@noUseSignals\s"#).unwrap());
+    static RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r#"(?:^|[\s*])@noUseSignals(?:\s|\*|$)"#).unwrap());
 
     RE.is_match(string)
+        || USE_SIGNALS_RE
+            .captures_iter(string)
+            .any(|it| it.get(1).is_some_and(|value| value.as_str() == "false"))
+}
+/// File-level directive prologue: `"use signals"`, `"use no signals"` or
+/// `"use signals <mode>"`
+fn get_mode_by_directive(directive: &str) -> Option<TransformMode> {
+    match directive {
+        "use signals" | "use signals all" => Some(TransformMode::All),
+        "use signals auto" => Some(TransformMode::Auto),
+        "use no signals" | "use signals manual" => Some(TransformMode::Manual),
+        _ => None,
+    }
 }
 fn get_default_import_source() -> Str {
     get_import_source("@preact-signals/safe-react/tracking")
@@ -57,8 +77,22 @@ fn get_named_import_ident() -> Ident {
         optional: false,
     }
 }
+fn get_use_hook_signals_import_ident() -> Ident {
+    Ident {
+        span: DUMMY_SP,
+        sym: "useHookSignals".into(),
+        optional: false,
+    }
+}
+fn get_track_class_signals_import_ident() -> Ident {
+    Ident {
+        span: DUMMY_SP,
+        sym: "trackClassSignals".into(),
+        optional: false,
+    }
+}
 
-#[derive(PartialEq, Eq, Deserialize)]
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 enum TransformMode {
     Manual,
@@ -70,7 +104,63 @@ enum TransformMode {
 #[serde(rename_all = "camelCase")]
 struct PreactSignalsPluginOptions {
     mode: Option<TransformMode>,
+    /// module exporting `useSignals`, `useHookSignals` and `trackClassSignals`
     import_source: Option<String>,
+    hocs: Option<Vec<String>>,
+    strict_hocs: Option<bool>,
+    /// transform mode for custom hooks (`use[A-Z]*` functions)
+    hooks: Option<TransformMode>,
+    /// functions which create elements, like `jsc.transform.react.pragma`
+    jsx_pragmas: Option<Vec<String>>,
+    /// modules, which named exports are signals, used by auto mode
+    signal_modules: Option<Vec<SignalModule>>,
+    /// accessors, which are counted as signal reads by auto mode
+    auto_detect: Option<AutoDetectOptions>,
+    /// globs of files to transform, matched against file name relative to
+    /// host's working directory
+    include: Option<Vec<String>>,
+    /// globs of files to return untouched
+    exclude: Option<Vec<String>>,
+    /// options for files matching globs, later entries win
+    overrides: Option<Vec<OptionsOverride>>,
+}
+
+#[derive(Deserialize)]
+struct OptionsOverride {
+    files: Vec<String>,
+    #[serde(flatten)]
+    options: PreactSignalsPluginOptions,
+}
+
+impl PreactSignalsPluginOptions {
+    /// Options set by `other` win, unset ones are inherited
+    fn merge(self, other: PreactSignalsPluginOptions) -> Self {
+        PreactSignalsPluginOptions {
+            mode: other.mode.or(self.mode),
+            import_source: other.import_source.or(self.import_source),
+            hocs: other.hocs.or(self.hocs),
+            strict_hocs: other.strict_hocs.or(self.strict_hocs),
+            hooks: other.hooks.or(self.hooks),
+            jsx_pragmas: other.jsx_pragmas.or(self.jsx_pragmas),
+            signal_modules: other.signal_modules.or(self.signal_modules),
+            auto_detect: other.auto_detect.or(self.auto_detect),
+            include: other.include.or(self.include),
+            exclude: other.exclude.or(self.exclude),
+            overrides: None,
+        }
+    }
+
+    /// Applies overrides, which `files` match file name, in order
+    fn resolve(mut self, filename: Option<&str>) -> Self {
+        let overrides = self.overrides.take().unwrap_or_default();
+        match filename {
+            Some(filename) => overrides
+                .into_iter()
+                .filter(|it| build_glob_set(&it.files).is_match(filename))
+                .fold(self, |acc, it| acc.merge(it.options)),
+            None => self,
+        }
+    }
 }
 
 pub struct SignalsTransformVisitor<C>
@@ -79,10 +169,32 @@ where
 {
     comments: C,
     mode: TransformMode,
+    hooks: TransformMode,
     import_use_signals: Option<Ident>,
+    import_use_hook_signals: Option<Ident>,
+    import_track_class_signals: Option<Ident>,
     use_signals_import_source: Str,
     ignore_span: Option<Span>,
-    is_file_named_like_component: bool,
+    /// component name inferred from file name, used for anonymous default exports
+    file_component_name: Option<String>,
+    hocs: HocFilter,
+    /// `hocs` with `strictHocs`, used for returned expressions: `items.map(function Row() {})`
+    /// is a callback, not a HOC
+    returned_hocs: HocFilter,
+    jsx_pragmas: JsxPragmas,
+    signal_modules: SignalModules,
+    auto_detect: AutoDetect,
+    /// resolver's mark of unresolved (global) identifiers
+    unresolved_mark: Option<Mark>,
+    signal_scope: SignalScope,
+    /// comment spans of declarations, which object literals are parts of
+    /// compound component (`Object.assign(Root, { Item })`)
+    compound_objects: HashMap<Span, Vec<Option<Span>>>,
+    /// comment spans of component declarations, used for static assignments
+    /// like `Menu.Item = () => <li />`
+    compound_bindings: HashMap<Atom, Vec<Option<Span>>>,
+    /// spans of class expressions already processed under name of their binding
+    processed_classes: HashSet<Span>,
 }
 
 impl<C> SignalsTransformVisitor<C>
@@ -94,54 +206,359 @@ where
             .get_or_insert(private_ident!("_useSignals"))
             .clone()
     }
+    fn get_import_use_hook_signals(&mut self) -> Ident {
+        self.import_use_hook_signals
+            .get_or_insert(private_ident!("_useHookSignals"))
+            .clone()
+    }
+    fn get_import_track_class_signals(&mut self) -> Ident {
+        self.import_track_class_signals
+            .get_or_insert(private_ident!("_trackClassSignals"))
+            .clone()
+    }
     fn from_options(
         options: PreactSignalsPluginOptions,
         comments: C,
-        is_file_named_like_component: bool,
+        file_component_name: Option<String>,
     ) -> Self {
+        let hocs = HocFilter::new(options.hocs, options.strict_hocs.unwrap_or(false));
         SignalsTransformVisitor {
             comments: comments,
-            is_file_named_like_component,
+            file_component_name,
             mode: options.mode.unwrap_or(TransformMode::All),
+            hooks: options.hooks.unwrap_or(TransformMode::Manual),
             import_use_signals: None,
+            import_use_hook_signals: None,
+            import_track_class_signals: None,
             use_signals_import_source: options
                 .import_source
                 .map(|it| get_import_source(it.as_str()))
                 .unwrap_or(get_default_import_source()),
             ignore_span: None,
+            returned_hocs: hocs.to_strict(),
+            hocs,
+            jsx_pragmas: JsxPragmas::new(options.jsx_pragmas),
+            signal_modules: SignalModules::new(options.signal_modules),
+            auto_detect: AutoDetect::new(options.auto_detect),
+            unresolved_mark: None,
+            signal_scope: SignalScope::default(),
+            compound_objects: HashMap::new(),
+            compound_bindings: HashMap::new(),
+            processed_classes: HashSet::new(),
         }
     }
-    fn from_default(comments: C, is_file_named_like_component: bool) -> Self {
+    fn from_default(comments: C, file_component_name: Option<String>) -> Self {
         SignalsTransformVisitor {
             comments: comments,
-            is_file_named_like_component,
+            file_component_name,
             mode: TransformMode::All,
+            hooks: TransformMode::Manual,
             import_use_signals: None,
+            import_use_hook_signals: None,
+            import_track_class_signals: None,
             use_signals_import_source: get_default_import_source(),
             ignore_span: None,
+            hocs: HocFilter::default(),
+            returned_hocs: HocFilter::default().to_strict(),
+            jsx_pragmas: JsxPragmas::default(),
+            signal_modules: SignalModules::default(),
+            auto_detect: AutoDetect::default(),
+            unresolved_mark: None,
+            signal_scope: SignalScope::default(),
+            compound_objects: HashMap::new(),
+            compound_bindings: HashMap::new(),
+            processed_classes: HashSet::new(),
+        }
+    }
+
+    fn with_unresolved_mark(mut self, unresolved_mark: Mark) -> Self {
+        self.unresolved_mark = Some(unresolved_mark);
+        self
+    }
+
+    /// Leading comment of the first import applies to the whole file:
+    /// `@useSignals` switches it to `all` mode and `@noUseSignals` to `manual` mode.
+    /// Comment of any other first statement applies to that statement only
+    fn get_file_mode_by_comment(&self, n: &Module) -> Option<TransformMode> {
+        match n
+            .body
+            .iter()
+            .find(|it| get_module_item_directive(it).is_none())
+        {
+            Some(ModuleItem::ModuleDecl(ModuleDecl::Import(import))) => {
+                self.get_mode_by_comment(&import.span)
+            }
+            _ => None,
+        }
+    }
+    /// The first `require` of CommonJS script plays role of the first import
+    fn get_script_mode_by_comment(&self, n: &Script) -> Option<TransformMode> {
+        n.body
+            .iter()
+            .find(|it| get_directive(it).is_none())
+            .and_then(get_require_span)
+            .and_then(|span| self.get_mode_by_comment(&span))
+    }
+    fn get_mode_by_comment(&self, span: &Span) -> Option<TransformMode> {
+        match should_track_by_comment(&self.comments, span) {
+            ShouldTrack::OptIn => Some(TransformMode::All),
+            ShouldTrack::OptOut => Some(TransformMode::Manual),
+            ShouldTrack::Auto => None,
+        }
+    }
+
+    /// Mode picked by file-level directive applies to that file only, opting
+    /// out disables custom hooks too
+    fn replace_modes(&mut self, mode: TransformMode) -> (TransformMode, TransformMode) {
+        let hooks = match mode {
+            TransformMode::Manual => TransformMode::Manual,
+            _ => self.hooks,
+        };
+        (
+            std::mem::replace(&mut self.mode, mode),
+            std::mem::replace(&mut self.hooks, hooks),
+        )
+    }
+
+    /// Signal reads are detected only in auto mode, so scope is collected
+    /// after file mode is resolved
+    fn collect_signal_scope<N>(&mut self, n: &N)
+    where
+        N: VisitWith<SignalScope> + for<'a> VisitWith<LocalFunctions<'a>>,
+    {
+        self.signal_scope = if self.mode == TransformMode::Auto || self.hooks == TransformMode::Auto
+        {
+            SignalScope::collect(
+                n,
+                self.unresolved_mark,
+                &self.signal_modules,
+                &self.auto_detect,
+            )
+        } else {
+            SignalScope::default()
+        };
+    }
+
+    fn process_var_decl(&mut self, n: &mut VarDecl, additional_spans: Option<&[Option<Span>]>) {
+        let decl_span = n.span;
+        // leading comment of statement applies to every declarator, comment placed
+        // before declarator applies only to it
+        for declarator in n.decls.iter_mut() {
+            if let Some(init) = &mut declarator.init {
+                let spans = [
+                    additional_spans.unwrap_or(&[]),
+                    &init.get_transparent_spans(),
+                    &[Some(declarator.span), Some(decl_span)],
+                ]
+                .concat();
+                self.register_compound_objects(init, &spans);
+                if let Pat::Ident(binding) = &declarator.name {
+                    self.register_compound_binding(&binding.id, &spans);
+                }
+                self.process_class_expr(init, Some(&declarator.name), &spans, false);
+
+                self.process_components(init, Some(&declarator.name), &[], &spans, false);
+            }
         }
     }
 
-    fn process_var_decl<T>(
+    /// Components are wrapped with `try/finally`, hooks only call `useHookSignals()`,
+    /// since they run inside of component render
+    fn wrap_function<W, I>(&mut self, n: &mut W, name: Option<&I>)
+    where
+        W: SignalWrappable,
+        I: MaybeComponentName,
+    {
+        if name.map(|it| it.is_hook_name()).unwrap_or(false) {
+            n.start_with_hook_signals(self.get_import_use_hook_signals())
+        } else {
+            n.wrap_with_use_signals(self.get_import_use_signals())
+        }
+    }
+
+    /// Tracks every component extracted from expression: `memo(() => {})`, both
+    /// branches of `cond ? A : B`. Own function name wins over `name` of binding or key
+    fn process_components<I>(
+        &mut self,
+        n: &mut Expr,
+        name: Option<&I>,
+        compound_spans: &[Option<Span>],
+        spans: &[Option<Span>],
+        is_default_export: bool,
+    ) where
+        I: MaybeComponentName,
+    {
+        let components = extract_fns_from_expr(n.unwrap_transparent_mut(), &self.hocs);
+        self.track_components(components, name, compound_spans, spans, is_default_export)
+    }
+    fn track_components<I>(
         &mut self,
-        n: &mut VarDecl,
-        additional_spans: Option<&[Option<Span>]>,
-        transform: T,
+        components: ExtractedFns,
+        name: Option<&I>,
+        compound_spans: &[Option<Span>],
+        spans: &[Option<Span>],
+        is_default_export: bool,
     ) where
-        T: FnOnce(&mut SignalsTransformVisitor<C>, &mut FunctionLike),
+        I: MaybeComponentName,
     {
-        if let Some(first) = n.decls.as_mut_slice().take_first_mut()
-            && let Some(init) = &mut first.init
-            && let child_span = init.unwrap_parens().get_span().clone()
-            && let Some(mut component) = extract_fn_from_expr(init.unwrap_parens_mut())
-            && let defaults_spans = [Some(child_span), Some(n.span)]
-            && let spans = [additional_spans.unwrap_or(&[]), &defaults_spans].concat()
-            && match component.get_fn_ident() {
-                None => self.should_track(&spans, &first.name, &component, false),
-                Some(ident) => self.should_track(&spans, &ident, &component, false),
+        for (mut component, chain_spans) in components {
+            let spans = [spans, &chain_spans].concat();
+            let fn_ident = component.get_fn_ident();
+            let should_track = match &fn_ident {
+                Some(ident) => self.should_track_component(
+                    Some(ident),
+                    compound_spans,
+                    &spans,
+                    &component,
+                    is_default_export,
+                ),
+                None => self.should_track_component(
+                    name,
+                    compound_spans,
+                    &spans,
+                    &component,
+                    is_default_export,
+                ),
+            };
+            if should_track {
+                match &fn_ident {
+                    Some(ident) => self.wrap_function(&mut component, Some(ident)),
+                    None => self.wrap_function(&mut component, name),
+                }
             }
+        }
+    }
+    fn should_track_component<I>(
+        &self,
+        name: Option<&I>,
+        compound_spans: &[Option<Span>],
+        spans: &[Option<Span>],
+        component: &FunctionLike,
+        is_default_export: bool,
+    ) -> bool
+    where
+        I: MaybeComponentName,
+    {
+        let compound_spans = self.get_compound_member_spans(compound_spans, name, component);
+        let spans = [compound_spans, spans].concat();
+        self.should_track_option_ident(&spans, name, component, is_default_export)
+    }
+
+    fn process_returned_component(&mut self, n: &mut Expr, additional_spans: &[Option<Span>]) {
+        let spans = [additional_spans, &n.get_transparent_spans()].concat();
+        let components = extract_fns_from_expr(n.unwrap_transparent_mut(), &self.returned_hocs);
+        self.track_components(components, None::<&Ident>, &[], &spans, false);
+    }
+
+    fn register_compound_objects(&mut self, expr: &Expr, spans: &[Option<Span>]) {
+        for span in get_compound_object_spans(expr) {
+            self.compound_objects.insert(span, spans.to_vec());
+        }
+    }
+    fn register_compound_binding(&mut self, ident: &Ident, spans: &[Option<Span>]) {
+        if ident.is_component_name() {
+            self.compound_bindings
+                .insert(ident.sym.clone(), spans.to_vec());
+        }
+    }
+
+    /// Opt-in of compound component applies only to members, which look like components:
+    /// named like components (any computed name `[key]` fits) and returning JSX.
+    /// `helper() {}`, `Menu.Extra = () => 10` or `Page.getLayout = function getLayout() {}`
+    /// are left untouched
+    fn get_compound_member_spans<'s, I, Comp>(
+        &self,
+        compound_spans: &'s [Option<Span>],
+        name: Option<&I>,
+        component: &Comp,
+    ) -> &'s [Option<Span>]
+    where
+        I: MaybeComponentName,
+        Comp: Detectable,
+    {
+        if compound_spans.is_empty()
+            || name.map_or(true, |it| it.is_dynamic_name() || it.is_component_name())
+                && component.has_jsx(&self.jsx_pragmas)
         {
-            transform(self, &mut component)
+            compound_spans
+        } else {
+            &[]
+        }
+    }
+
+    fn process_key_value_prop(&mut self, n: &mut KeyValueProp, compound_spans: &[Option<Span>]) {
+        let key_span = Some(*n.key.get_span());
+        let spans = [compound_spans, &[key_span]].concat();
+        self.process_class_expr(&mut n.value, Some(&n.key), &spans, false);
+        self.process_components(
+            &mut n.value,
+            Some(&n.key),
+            compound_spans,
+            &[key_span],
+            false,
+        );
+    }
+    fn process_method_prop(&mut self, n: &mut MethodProp, compound_spans: &[Option<Span>]) {
+        let compound_spans =
+            self.get_compound_member_spans(compound_spans, Some(&n.key), n.function.deref());
+        let spans = [
+            compound_spans,
+            &[Some(n.function.span), Some(*n.key.get_span())],
+        ]
+        .concat();
+        if self.should_track(&spans, &n.key, n.function.deref(), false) {
+            self.wrap_function(n.function.deref_mut(), Some(&n.key))
+        }
+    }
+
+    fn process_class<I>(
+        &mut self,
+        class: &mut Class,
+        ident: Option<&I>,
+        additional_spans: &[Option<Span>],
+        is_default_export: bool,
+    ) where
+        I: MaybeComponentName,
+    {
+        let class_span = class.span;
+        if let Some(render) = find_render_method(class)
+            && let defaults_spans = [
+                Some(class_span),
+                Some(render.span),
+                Some(render.function.span),
+            ]
+            && let spans = [additional_spans, &defaults_spans].concat()
+            && self.should_track_option_ident(
+                &spans,
+                ident,
+                render.function.deref(),
+                is_default_export,
+            )
+            && let Some(body) = &mut render.function.body
+        {
+            body.stmts =
+                wrap_with_track_class_signals(&body.stmts, self.get_import_track_class_signals());
+        }
+    }
+
+    /// Class expressions are tracked under name of binding or key they are assigned to
+    fn process_class_expr<I>(
+        &mut self,
+        n: &mut Expr,
+        name: Option<&I>,
+        additional_spans: &[Option<Span>],
+        is_default_export: bool,
+    ) where
+        I: MaybeComponentName,
+    {
+        if let Expr::Class(ClassExpr { ident, class }) = n.unwrap_transparent_mut() {
+            match ident {
+                Some(ident) => {
+                    self.process_class(class, Some(&*ident), additional_spans, is_default_export)
+                }
+                None => self.process_class(class, name, additional_spans, is_default_export),
+            }
+            self.processed_classes.insert(class.span);
         }
     }
 }
@@ -161,16 +578,10 @@ where
         Some(item) => {
             let is_track_signals = item
                 .iter()
-                .find(|it| {
-                    it.kind == CommentKind::Block && is_track_signals_directive(it.text.as_str())
-                })
-                .is_some();
+                .any(|it| is_track_signals_directive(it.text.as_str()));
             let is_no_track_signals = item
                 .iter()
-                .find(|it| {
-                    it.kind == CommentKind::Block && is_no_track_signals_directive(it.text.as_str())
-                })
-                .is_some();
+                .any(|it| is_no_track_signals_directive(it.text.as_str()));
 
             match (is_track_signals, is_no_track_signals) {
                 (true, true) => {
@@ -199,12 +610,26 @@ where
             },
             None => None,
         })
-        .reduce(|acc, it| match (acc, it) {
-            (ShouldTrack::OptIn, ShouldTrack::OptOut) => ShouldTrack::OptOut,
-            (ShouldTrack::OptOut, ShouldTrack::OptIn) => ShouldTrack::OptOut,
-            (_, it) => it,
+        .fold(ShouldTrack::Auto, merge_should_track)
+}
+/// Function-body directives: `"use signals"` and `"use no signals"`
+fn should_track_by_directives(directives: &[&str]) -> ShouldTrack {
+    directives
+        .iter()
+        .map(|it| match *it {
+            "use signals" => ShouldTrack::OptIn,
+            "use no signals" => ShouldTrack::OptOut,
+            _ => ShouldTrack::Auto,
         })
-        .unwrap_or(ShouldTrack::Auto)
+        .fold(ShouldTrack::Auto, merge_should_track)
+}
+fn merge_should_track(acc: ShouldTrack, it: ShouldTrack) -> ShouldTrack {
+    match (acc, it) {
+        (ShouldTrack::OptIn, ShouldTrack::OptOut) => ShouldTrack::OptOut,
+        (ShouldTrack::OptOut, ShouldTrack::OptIn) => ShouldTrack::OptOut,
+        (acc, ShouldTrack::Auto) => acc,
+        (_, it) => it,
+    }
 }
 
 impl<C> SignalsTransformVisitor<C>
@@ -221,18 +646,30 @@ where
         Comp: Detectable,
         I: MaybeComponentName,
     {
+        if ident.map(|it| it.is_hook_name()).unwrap_or(false) {
+            return match self.hooks {
+                TransformMode::All => true,
+                TransformMode::Manual => false,
+                TransformMode::Auto => component.has_dot_value(&self.signal_scope),
+            };
+        }
         let should_track_by_name = || {
             if is_default_export && ident.is_none() {
-                self.is_file_named_like_component
+                self.file_component_name
+                    .as_ref()
+                    .map(|it| it.as_str().is_component_name())
+                    .unwrap_or(false)
             } else {
                 ident.map(|it| it.is_component_name()).unwrap_or(false)
             }
         };
         match self.mode {
-            TransformMode::All => should_track_by_name() && component.has_jsx(),
+            TransformMode::All => should_track_by_name() && component.has_jsx(&self.jsx_pragmas),
             TransformMode::Manual => false,
             TransformMode::Auto => {
-                should_track_by_name() && component.has_jsx() && component.has_dot_value()
+                should_track_by_name()
+                    && component.has_jsx(&self.jsx_pragmas)
+                    && component.has_dot_value(&self.signal_scope)
             }
         }
     }
@@ -249,7 +686,10 @@ where
         Comp: Detectable,
         I: MaybeComponentName,
     {
-        match should_track_by_comments(&self.comments, comment_spans) {
+        match merge_should_track(
+            should_track_by_comments(&self.comments, comment_spans),
+            should_track_by_directives(&component.get_directives()),
+        ) {
             ShouldTrack::Auto => self.should_track_auto(ident, component, is_default_export),
             ShouldTrack::OptIn => true,
             ShouldTrack::OptOut => false,
@@ -282,9 +722,7 @@ where
             .unwrap_or(true);
 
         if should_process {
-            self.process_var_decl(n, None, |mut_self, it| {
-                it.wrap_with_use_signals(mut_self.get_import_use_signals())
-            })
+            self.process_var_decl(n, None)
         }
 
         n.visit_mut_children_with(self);
@@ -295,11 +733,7 @@ where
                 span,
                 decl: Decl::Var(ref mut var_decl),
             } => {
-                self.process_var_decl(
-                    var_decl.deref_mut(),
-                    Some(&[Some(*span)]),
-                    |mut_self, it| it.wrap_with_use_signals(mut_self.get_import_use_signals()),
-                );
+                self.process_var_decl(var_decl.deref_mut(), Some(&[Some(*span)]));
                 let old_span = self.ignore_span;
                 self.ignore_span = Some(var_decl.span.clone());
                 n.visit_mut_children_with(self);
@@ -309,21 +743,38 @@ where
                 span,
                 decl: Decl::Fn(ref mut fn_declr),
             } => {
+                self.register_compound_binding(
+                    &fn_declr.ident,
+                    &[Some(*span), Some(fn_declr.function.span)],
+                );
                 if self.should_track(
                     &[Some(span.clone()), Some(fn_declr.function.span)],
                     &fn_declr.ident,
                     fn_declr,
                     false,
                 ) {
-                    fn_declr
-                        .function
-                        .wrap_with_use_signals(self.get_import_use_signals())
+                    self.wrap_function(fn_declr.function.deref_mut(), Some(&fn_declr.ident))
                 }
                 let old_span = self.ignore_span;
                 self.ignore_span = Some(fn_declr.function.span.clone());
                 n.visit_mut_children_with(self);
                 self.ignore_span = old_span
             }
+            ExportDecl {
+                span,
+                decl: Decl::Class(ref mut class_decl),
+            } => {
+                self.process_class(
+                    &mut class_decl.class,
+                    Some(&class_decl.ident),
+                    &[Some(*span)],
+                    false,
+                );
+                let old_span = self.ignore_span;
+                self.ignore_span = Some(class_decl.class.span);
+                n.visit_mut_children_with(self);
+                self.ignore_span = old_span
+            }
             n => n.visit_mut_children_with(self),
         }
     }
@@ -339,33 +790,37 @@ where
                     fn_expr,
                     true,
                 ) {
-                    fn_expr
-                        .function
-                        .wrap_with_use_signals(self.get_import_use_signals())
+                    self.wrap_function(fn_expr.function.deref_mut(), fn_expr.ident.as_ref())
                 }
                 let old_span = self.ignore_span;
                 self.ignore_span = Some(fn_expr.function.span.clone());
                 n.visit_mut_children_with(self);
                 self.ignore_span = old_span
             }
+            ExportDefaultDecl {
+                span,
+                decl: DefaultDecl::Class(ref mut class_expr),
+            } => {
+                self.process_class(
+                    &mut class_expr.class,
+                    class_expr.ident.as_ref(),
+                    &[Some(*span)],
+                    true,
+                );
+                let old_span = self.ignore_span;
+                self.ignore_span = Some(class_expr.class.span);
+                n.visit_mut_children_with(self);
+                self.ignore_span = old_span
+            }
             n => n.visit_mut_children_with(self),
         }
     }
     fn visit_mut_export_default_expr(&mut self, n: &mut ExportDefaultExpr) {
         match n {
             ExportDefaultExpr { span, ref mut expr } => {
-                let child_span = expr.unwrap_parens().get_span().clone();
-                if let Some(mut component) = extract_fn_from_expr(expr.unwrap_parens_mut())
-                    && let spans = [Some(span.clone()), Some(child_span), Some(n.span)]
-                    && self.should_track_option_ident(
-                        &spans,
-                        component.get_fn_ident().as_ref(),
-                        &component,
-                        true,
-                    )
-                {
-                    component.wrap_with_use_signals(self.get_import_use_signals());
-                }
+                let spans = [[Some(*span)].as_slice(), &expr.get_transparent_spans()].concat();
+                self.register_compound_objects(expr, &spans);
+                self.process_components(expr, None::<&Ident>, &[], &spans, true);
 
                 n.visit_mut_children_with(self);
                 // let old_span = self.ignore_span;
@@ -375,62 +830,186 @@ where
         }
     }
     fn visit_mut_fn_decl(&mut self, n: &mut FnDecl) {
-        if match self.ignore_span {
+        let should_process = match self.ignore_span {
             Some(span) => !span.eq(&n.function.span),
             None => true,
-        } && self.should_track(&[Some(n.function.span)], &n.ident, n, false)
+        };
+        if should_process {
+            self.register_compound_binding(&n.ident, &[Some(n.function.span)]);
+        }
+        if should_process && self.should_track(&[Some(n.function.span)], &n.ident, n, false) {
+            self.wrap_function(n.function.deref_mut(), Some(&n.ident))
+        }
+        n.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_function(&mut self, n: &mut Function) {
+        if let Some(body) = &mut n.body {
+            remove_signals_directives(&mut body.stmts);
+        }
+        n.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_class_decl(&mut self, n: &mut ClassDecl) {
+        if match self.ignore_span {
+            Some(span) => !span.eq(&n.class.span),
+            None => true,
+        } {
+            self.process_class(&mut n.class, Some(&n.ident), &[], false);
+        }
+        n.visit_mut_children_with(self);
+    }
+    fn visit_mut_class_expr(&mut self, n: &mut ClassExpr) {
+        if match self.ignore_span {
+            Some(span) => !span.eq(&n.class.span),
+            None => true,
+        } && !self.processed_classes.contains(&n.class.span)
         {
-            n.function
-                .wrap_with_use_signals(self.get_import_use_signals())
+            self.process_class(&mut n.class, n.ident.as_ref(), &[], false);
         }
         n.visit_mut_children_with(self);
     }
 
     fn visit_mut_assign_expr(&mut self, n: &mut AssignExpr) {
-        if let Some(mut component) = extract_fn_from_expr(n.right.borrow_mut())
-            && match component.get_fn_ident() {
-                None => self.should_track(&[Some(n.span)], &n.left, &component, false),
-                Some(ident) => self.should_track(&[Some(n.span)], &ident, &component, false),
-            }
-        {
-            component.wrap_with_use_signals(self.get_import_use_signals())
+        let compound_spans = get_assign_root_ident(&n.left)
+            .and_then(|it| self.compound_bindings.get(&it.sym))
+            .cloned()
+            .unwrap_or_default();
+        let spans = [compound_spans.as_slice(), &[Some(n.span)]].concat();
+        self.register_compound_objects(&n.right, &spans);
+        let is_default_export = is_commonjs_default_export(&n.left);
+        let own_spans = [Some(n.span)];
+        if is_default_export {
+            self.process_class_expr(&mut n.right, None::<&Ident>, &spans, true);
+            self.process_components(
+                &mut n.right,
+                None::<&Ident>,
+                &compound_spans,
+                &own_spans,
+                true,
+            );
+        } else {
+            self.process_class_expr(&mut n.right, Some(&n.left), &spans, false);
+            self.process_components(
+                &mut n.right,
+                Some(&n.left),
+                &compound_spans,
+                &own_spans,
+                false,
+            );
+        }
+
+        n.visit_mut_children_with(self);
+    }
+    fn visit_mut_return_stmt(&mut self, n: &mut ReturnStmt) {
+        if let Some(arg) = &mut n.arg {
+            self.process_returned_component(arg, &[Some(n.span)]);
+        }
+
+        n.visit_mut_children_with(self);
+    }
+    fn visit_mut_arrow_expr(&mut self, n: &mut ArrowExpr) {
+        match n.body.deref_mut() {
+            BlockStmtOrExpr::Expr(body) => self.process_returned_component(body, &[]),
+            BlockStmtOrExpr::BlockStmt(block) => remove_signals_directives(&mut block.stmts),
         }
 
         n.visit_mut_children_with(self);
     }
     fn visit_mut_key_value_prop(&mut self, n: &mut KeyValueProp) {
-        if let Some(mut component) = extract_fn_from_expr(&mut n.value)
-            && self.should_track(
-                &[Some(*n.key.get_span())],
-                &component
-                    .get_fn_ident()
-                    .map_or(n.key.clone(), |it| PropName::Ident(it)),
-                &component,
-                false,
-            )
-        {
-            component.wrap_with_use_signals(self.get_import_use_signals())
+        self.process_key_value_prop(n, &[]);
+
+        n.visit_mut_children_with(self);
+    }
+    fn visit_mut_class_prop(&mut self, n: &mut ClassProp) {
+        let spans = [Some(n.span), Some(*n.key.get_span())];
+        if let Some(value) = &mut n.value {
+            self.process_components(value, Some(&n.key), &[], &spans, false);
         }
 
         n.visit_mut_children_with(self);
     }
-    fn visit_mut_method_prop(&mut self, n: &mut MethodProp) {
-        if self.should_track(
-            &[n.function.span.into(), n.key.get_span().clone().into()],
-            &n.key,
-            n.function.deref(),
-            false,
-        ) {
-            n.function
-                .wrap_with_use_signals(self.get_import_use_signals())
+    fn visit_mut_private_prop(&mut self, n: &mut PrivateProp) {
+        let spans = [Some(n.span), Some(n.key.span)];
+        if let Some(value) = &mut n.value {
+            self.process_components(value, Some(&n.key), &[], &spans, false);
         }
 
         n.visit_mut_children_with(self);
     }
+    fn visit_mut_method_prop(&mut self, n: &mut MethodProp) {
+        self.process_method_prop(n, &[]);
+
+        n.visit_mut_children_with(self);
+    }
+    fn visit_mut_object_lit(&mut self, n: &mut ObjectLit) {
+        match self.compound_objects.remove(&n.span) {
+            Some(spans) => {
+                for prop in n.props.iter_mut() {
+                    match prop {
+                        PropOrSpread::Prop(prop) => match prop.deref_mut() {
+                            Prop::KeyValue(key_value) => {
+                                self.process_key_value_prop(key_value, &spans);
+                                key_value.visit_mut_children_with(self);
+                            }
+                            Prop::Method(method) => {
+                                self.process_method_prop(method, &spans);
+                                method.visit_mut_children_with(self);
+                            }
+                            prop => prop.visit_mut_with(self),
+                        },
+                        prop => prop.visit_mut_with(self),
+                    }
+                }
+            }
+            None => n.visit_mut_children_with(self),
+        }
+    }
 
     fn visit_mut_module(&mut self, n: &mut Module) {
         self.import_use_signals = None;
+        self.import_use_hook_signals = None;
+        self.import_track_class_signals = None;
+        self.jsx_pragmas.collect_runtime_imports(n);
+        let file_mode = n
+            .body
+            .iter()
+            .map_while(get_module_item_directive)
+            .find_map(get_mode_by_directive)
+            .or_else(|| self.get_file_mode_by_comment(n));
+        let modes = file_mode.map(|mode| self.replace_modes(mode));
+        self.collect_signal_scope(n);
         n.visit_mut_children_with(self);
+        if let Some((mode, hooks)) = modes {
+            self.mode = mode;
+            self.hooks = hooks;
+        }
+        if let Some(ident) = &self.import_use_hook_signals {
+            prepend_stmt(
+                &mut n.body,
+                ModuleItem::ModuleDecl(
+                    add_import(
+                        ident.clone(),
+                        self.use_signals_import_source.clone(),
+                        get_use_hook_signals_import_ident().into(),
+                    )
+                    .into(),
+                ),
+            )
+        }
+        if let Some(ident) = &self.import_track_class_signals {
+            prepend_stmt(
+                &mut n.body,
+                ModuleItem::ModuleDecl(
+                    add_import(
+                        ident.clone(),
+                        self.use_signals_import_source.clone(),
+                        get_track_class_signals_import_ident().into(),
+                    )
+                    .into(),
+                ),
+            )
+        }
         if let Some(ident) = &self.import_use_signals {
             prepend_stmt(
                 &mut n.body,
@@ -448,8 +1027,43 @@ where
 
     fn visit_mut_script(&mut self, n: &mut Script) {
         self.import_use_signals = None;
+        self.import_use_hook_signals = None;
+        self.import_track_class_signals = None;
+        self.jsx_pragmas.collect_script_runtime_imports(n);
+        let file_mode = n
+            .body
+            .iter()
+            .map_while(get_directive)
+            .find_map(get_mode_by_directive)
+            .or_else(|| self.get_script_mode_by_comment(n));
+        let modes = file_mode.map(|mode| self.replace_modes(mode));
+        self.collect_signal_scope(n);
         n.visit_mut_children_with(self);
+        if let Some((mode, hooks)) = modes {
+            self.mode = mode;
+            self.hooks = hooks;
+        }
 
+        if let Some(ident) = &self.import_use_hook_signals {
+            prepend_stmt(
+                &mut n.body,
+                add_require(
+                    ident.clone(),
+                    self.use_signals_import_source.clone(),
+                    get_use_hook_signals_import_ident().into(),
+                ),
+            )
+        }
+        if let Some(ident) = &self.import_track_class_signals {
+            prepend_stmt(
+                &mut n.body,
+                add_require(
+                    ident.clone(),
+                    self.use_signals_import_source.clone(),
+                    get_track_class_signals_import_ident().into(),
+                ),
+            )
+        }
         if let Some(ident) = &self.import_use_signals {
             prepend_stmt(
                 &mut n.body,
@@ -480,37 +1094,38 @@ where
 /// Refer swc_plugin_macro to see how does it work internally.
 #[plugin_transform]
 pub fn process_transform(program: Program, _metadata: TransformPluginProgramMetadata) -> Program {
+    let filename = _metadata.get_context(&TransformPluginMetadataContextKind::Filename);
+    let cwd = _metadata.get_context(&TransformPluginMetadataContextKind::Cwd);
+    let relative_path = filename
+        .as_deref()
+        .map(|it| get_relative_path(it, cwd.as_deref()));
+    let options = _metadata.get_transform_plugin_config().map(|data| {
+        serde_json::from_str::<PreactSignalsPluginOptions>(data.as_str())
+            .expect("transform plugin config should be valid json")
+            .resolve(relative_path.as_deref())
+    });
+
+    if let Some(options) = &options
+        && let Some(relative_path) = &relative_path
+        && !PathFilter::new(options.include.as_deref(), options.exclude.as_deref())
+            .allows(relative_path.as_str())
+    {
+        return program;
+    }
+
     program.fold_with(&mut as_folder({
-        let data = _metadata.get_transform_plugin_config();
-
-        let is_file_named_like_component = _metadata
-            .get_context(&TransformPluginMetadataContextKind::Filename)
-            .map(|it| {
-                PathBuf::from(it)
-                    .file_name()
-                    .map(|it| it.to_str())
-                    .flatten()
-                    .map(|it| it.to_owned())
-            })
-            .flatten()
-            .map(|it| it.is_component_name())
-            .unwrap_or(false);
-
-        match data {
-            Some(data) => {
-                let options = serde_json::from_str::<PreactSignalsPluginOptions>(data.as_str())
-                    .expect("transform plugin config should be valid json");
-                SignalsTransformVisitor::from_options(
-                    options,
-                    _metadata.comments,
-                    is_file_named_like_component,
-                )
-            }
-            None => SignalsTransformVisitor::from_default(
+        let file_component_name =
+            filename.and_then(|it| get_component_name_from_path(&PathBuf::from(it)));
+
+        match options {
+            Some(options) => SignalsTransformVisitor::from_options(
+                options,
                 _metadata.comments,
-                is_file_named_like_component,
+                file_component_name,
             ),
+            None => SignalsTransformVisitor::from_default(_metadata.comments, file_component_name),
         }
+        .with_unresolved_mark(_metadata.unresolved_mark)
     }))
 }
 
@@ -523,11 +1138,37 @@ fn get_syntax() -> Syntax {
     Syntax::Es(a)
 }
 
+#[cfg(test)]
+fn get_ts_syntax() -> Syntax {
+    use swc_core::ecma::parser::TsConfig;
+
+    Syntax::Typescript(TsConfig {
+        tsx: true,
+        ..Default::default()
+    })
+}
+
+/// `test_inline` parses input as module, CommonJS tests run transform on it as on script
+#[cfg(test)]
+struct AsScript<V>(V);
+#[cfg(test)]
+impl<V: VisitMut> VisitMut for AsScript<V> {
+    fn visit_mut_module(&mut self, n: &mut Module) {
+        let mut script = Script {
+            span: n.span,
+            body: n.body.drain(..).map(ModuleItem::expect_stmt).collect(),
+            shebang: n.shebang.take(),
+        };
+        self.0.visit_mut_script(&mut script);
+        n.body = script.body.into_iter().map(ModuleItem::Stmt).collect();
+    }
+}
+
 test_inline!(
     get_syntax(),
     |tester| as_folder(SignalsTransformVisitor::from_default(
         tester.comments.clone(),
-        false
+        None
     )),
     arrow_fn,
     // Input codes
@@ -563,7 +1204,7 @@ test_inline!(
     get_syntax(),
     |tester| as_folder(SignalsTransformVisitor::from_default(
         tester.comments.clone(),
-        false
+        None
     )),
     function,
     // Input codes
@@ -591,7 +1232,7 @@ test_inline!(
     get_syntax(),
     |tester| as_folder(SignalsTransformVisitor::from_default(
         tester.comments.clone(),
-        false
+        None
     )),
     function_expr,
     // Input codes
@@ -629,7 +1270,7 @@ test_inline!(
     get_syntax(),
     |tester| as_folder(SignalsTransformVisitor::from_default(
         tester.comments.clone(),
-        false
+        None
     )),
     opt_in,
     // Input codes
@@ -731,7 +1372,7 @@ test_inline!(
     get_syntax(),
     |tester| as_folder(SignalsTransformVisitor::from_default(
         tester.comments.clone(),
-        false
+        None
     )),
     nested_components,
     // Input codes
@@ -789,7 +1430,7 @@ test_inline!(
     get_syntax(),
     |tester| as_folder(SignalsTransformVisitor::from_default(
         tester.comments.clone(),
-        false
+        None
     )),
     hocs,
     // Input codes
@@ -828,7 +1469,7 @@ test_inline!(
     get_syntax(),
     |tester| as_folder(SignalsTransformVisitor::from_default(
         tester.comments.clone(),
-        false
+        None
     )),
     opt_in_opt_out,
     // Input codes
@@ -853,6 +1494,77 @@ function MyComponent() {
 "#
 );
 
+// Directives are recognised in block and line comments, at the start, in the
+// middle or at the end of comment. `@useSignals false` is an alias for
+// `@noUseSignals`
+test_inline!(
+    get_syntax(),
+    |tester| as_folder(SignalsTransformVisitor::from_default(
+        tester.comments.clone(),
+        None
+    )),
+    opt_in_directive_grammar,
+    // Input codes
+    r#"
+// @useSignals
+function lineComment() {
+    return <div>{signal.value}</div>;
+}
+/**@useSignals*/
+function compact() {
+    return <div>{signal.value}</div>;
+}
+/* @useSignals*/
+function compactEnd() {
+    return <div>{signal.value}</div>;
+}
+// @noUseSignals
+function LineOptOut() {
+    return <div>{signal.value}</div>;
+}
+/** @useSignals false */
+function FalseOptOut() {
+    return <div>{signal.value}</div>;
+}
+"#,
+    // Expected codes
+    r#"
+import { useSignals as _useSignals } from "@preact-signals/safe-react/tracking";
+// @useSignals
+function lineComment() {
+    var _effect = _useSignals();
+    try {
+        return <div>{signal.value}</div>;
+    } finally{
+        _effect.f();
+    }
+}
+/**@useSignals*/ function compact() {
+    var _effect = _useSignals();
+    try {
+        return <div>{signal.value}</div>;
+    } finally{
+        _effect.f();
+    }
+}
+/* @useSignals*/ function compactEnd() {
+    var _effect = _useSignals();
+    try {
+        return <div>{signal.value}</div>;
+    } finally{
+        _effect.f();
+    }
+}
+// @noUseSignals
+function LineOptOut() {
+    return <div>{signal.value}</div>;
+}
+/** @useSignals false */ function FalseOptOut() {
+    return <div>{signal.value}</div>;
+}
+"#
+);
+
 // An example to test plugin transform.
 // Recommended strategy to test plugin's transform is verify
 // the Visitor's behavior, instead of trying to run `process_transform` with mocks
@@ -861,7 +1573,7 @@ test_inline!(
     get_syntax(),
     |tester| as_folder(SignalsTransformVisitor::from_default(
         tester.comments.clone(),
-        false
+        None
     )),
     rare_components,
     // Input codes
@@ -956,7 +1668,7 @@ test_inline!(
     get_syntax(),
     |tester| as_folder(SignalsTransformVisitor::from_default(
         tester.comments.clone(),
-        true
+        Some("Component".to_owned())
     )),
     default_components,
     // Input codes
@@ -995,7 +1707,7 @@ test_inline!(
     get_syntax(),
     |tester| as_folder(SignalsTransformVisitor::from_default(
         tester.comments.clone(),
-        false
+        None
     )),
     import_goes_after_directives,
     // Input codes
@@ -1019,3 +1731,1719 @@ const Bebe = ()=>{
 }
 "#
 );
+
+test_inline!(
+    get_syntax(),
+    |tester| as_folder(SignalsTransformVisitor::from_default(
+        tester.comments.clone(),
+        None
+    )),
+    class_components,
+    // Input codes
+    r#"
+class Counter extends React.Component {
+    render() {
+        return <div>{count.value}</div>
+    }
+}
+const Named = class Inner extends Component {
+    render() {
+        return <span />
+    }
+}
+class store {
+    render() {
+        return <div />
+    }
+}
+/**
+ * @noUseSignals
+ */
+class Skipped extends React.Component {
+    render() {
+        return <div />
+    }
+}
+export class Exported extends React.Component {
+    static render() {
+        return <div />
+    }
+    render() {
+        return <div />
+    }
+}
+const Anonymous = class extends React.Component {
+    render() {
+        return <div>{count.value}</div>
+    }
+}
+Widgets.Card = class extends React.Component {
+    render() {
+        return <div />
+    }
+}
+const widgets = {
+    Panel: class extends React.Component {
+        render() {
+            return <div />
+        }
+    }
+}
+const helper = class {
+    render() {
+        return <div />
+    }
+}
+"#,
+    // Expected codes
+    r#"
+import { trackClassSignals as _trackClassSignals } from "@preact-signals/safe-react/tracking";
+class Counter extends React.Component {
+    render() {
+        var _effect = _trackClassSignals(this);
+        try {
+            return <div>{count.value}</div>;
+        } finally{
+            _effect.f();
+        }
+    }
+}
+const Named = class Inner extends Component {
+    render() {
+        var _effect = _trackClassSignals(this);
+        try {
+            return <span/>;
+        } finally{
+            _effect.f();
+        }
+    }
+};
+class store {
+    render() {
+        return <div/>;
+    }
+}
+/**
+ * @noUseSignals
+ */
+class Skipped extends React.Component {
+    render() {
+        return <div/>;
+    }
+}
+export class Exported extends React.Component {
+    static render() {
+        return <div/>;
+    }
+    render() {
+        var _effect = _trackClassSignals(this);
+        try {
+            return <div/>;
+        } finally{
+            _effect.f();
+        }
+    }
+}
+const Anonymous = class extends React.Component {
+    render() {
+        var _effect = _trackClassSignals(this);
+        try {
+            return <div>{count.value}</div>;
+        } finally{
+            _effect.f();
+        }
+    }
+};
+Widgets.Card = class extends React.Component {
+    render() {
+        var _effect = _trackClassSignals(this);
+        try {
+            return <div/>;
+        } finally{
+            _effect.f();
+        }
+    }
+};
+const widgets = {
+    Panel: class extends React.Component {
+        render() {
+            var _effect = _trackClassSignals(this);
+            try {
+                return <div/>;
+            } finally{
+                _effect.f();
+            }
+        }
+    }
+};
+const helper = class {
+    render() {
+        return <div/>;
+    }
+};
+"#
+);
+
+test_inline!(
+    get_syntax(),
+    |tester| as_folder(SignalsTransformVisitor::from_options(
+        serde_json::from_str(r#"{ "importSource": "@app/tracking" }"#).unwrap(),
+        tester.comments.clone(),
+        None
+    )),
+    custom_import_source,
+    // Input codes
+    r#"
+const Counter = () => <div>{count.value}</div>
+class Label extends React.Component {
+    render() {
+        return <span>{label.value}</span>
+    }
+}
+"#,
+    // Expected codes
+    r#"
+import { useSignals as _useSignals } from "@app/tracking";
+import { trackClassSignals as _trackClassSignals } from "@app/tracking";
+const Counter = ()=>{
+    var _effect = _useSignals();
+    try {
+        return <div>{count.value}</div>;
+    } finally{
+        _effect.f();
+    }
+};
+class Label extends React.Component {
+    render() {
+        var _effect = _trackClassSignals(this);
+        try {
+            return <span>{label.value}</span>;
+        } finally{
+            _effect.f();
+        }
+    }
+}
+"#
+);
+
+test_inline!(
+    get_syntax(),
+    |tester| as_folder(SignalsTransformVisitor::from_default(
+        tester.comments.clone(),
+        None
+    )),
+    class_prop_components,
+    // Input codes
+    r#"
+class Page {
+    static Header = () => <h1>{title.value}</h1>;
+    Row = function() {
+        return <li />
+    };
+    ['Footer'] = () => <footer />;
+    handler = () => <div />;
+    #Private = () => <div />;
+    /**
+     * @useSignals
+     */
+    #optIn = () => <div />;
+}
+"#,
+    // Expected codes
+    r#"
+import { useSignals as _useSignals } from "@preact-signals/safe-react/tracking";
+class Page {
+    static Header = ()=>{
+        var _effect = _useSignals();
+        try {
+            return <h1>{title.value}</h1>;
+        } finally{
+            _effect.f();
+        }
+    };
+    Row = function() {
+        var _effect = _useSignals();
+        try {
+            return <li/>;
+        } finally{
+            _effect.f();
+        }
+    };
+    ['Footer'] = ()=>{
+        var _effect = _useSignals();
+        try {
+            return <footer/>;
+        } finally{
+            _effect.f();
+        }
+    };
+    handler = ()=><div/>;
+    #Private = ()=><div/>;
+    #optIn = ()=>{
+        var _effect = _useSignals();
+        try {
+            return <div/>;
+        } finally{
+            _effect.f();
+        }
+    };
+}
+"#
+);
+
+test_inline!(
+    get_syntax(),
+    |tester| as_folder(SignalsTransformVisitor::from_default(
+        tester.comments.clone(),
+        None
+    )),
+    curried_hocs,
+    // Input codes
+    r#"
+const Connected = connect(mapState)(function Foo() {
+    return <div>{a.value}</div>
+})
+const Styled = styled("div", () => <div />)
+const Observed = observer(options, () => {
+    return <div />
+})
+/** @useSignals */
+const Compared = memo(Inner, (a, b) => a.id === b.id)
+/** @useSignals */
+const MemberCompared = memo(ui.Inner, (a, b) => a.id === b.id)
+/** @useSignals */
+const ConnectedInner = connect((state) => state.user)(Inner)
+"#,
+    // Expected codes
+    r#"
+import { useSignals as _useSignals } from "@preact-signals/safe-react/tracking";
+const Connected = connect(mapState)(function Foo() {
+    var _effect = _useSignals();
+    try {
+        return <div>{a.value}</div>;
+    } finally{
+        _effect.f();
+    }
+});
+const Styled = styled("div", ()=>{
+    var _effect = _useSignals();
+    try {
+        return <div/>;
+    } finally{
+        _effect.f();
+    }
+});
+const Observed = observer(options, ()=>{
+    var _effect = _useSignals();
+    try {
+        return <div/>;
+    } finally{
+        _effect.f();
+    }
+});
+/** @useSignals */ const Compared = memo(Inner, (a, b)=>a.id === b.id);
+/** @useSignals */ const MemberCompared = memo(ui.Inner, (a, b)=>a.id === b.id);
+/** @useSignals */ const ConnectedInner = connect((state)=>state.user)(Inner);
+"#
+);
+
+test_inline!(
+    get_syntax(),
+    |tester| as_folder(SignalsTransformVisitor::from_options(
+        serde_json::from_str(
+            r#"{ "strictHocs": true, "hocs": ["memo", "React.forwardRef", "withTheme"] }"#
+        )
+        .unwrap(),
+        tester.comments.clone(),
+        None
+    )),
+    strict_hocs,
+    // Input codes
+    r#"
+const Items = list.map(() => <li />)
+const Handler = useCallback(() => <X />, [])
+const Memoized = memo(() => <div />)
+const Ref = React.forwardRef((props, ref) => <div ref={ref} />)
+const Themed = withTheme(() => <div />)
+"#,
+    // Expected codes
+    r#"
+import { useSignals as _useSignals } from "@preact-signals/safe-react/tracking";
+const Items = list.map(()=><li/>);
+const Handler = useCallback(()=><X/>, []);
+const Memoized = memo(()=>{
+    var _effect = _useSignals();
+    try {
+        return <div/>;
+    } finally{
+        _effect.f();
+    }
+});
+const Ref = React.forwardRef((props, ref)=>{
+    var _effect = _useSignals();
+    try {
+        return <div ref={ref}/>;
+    } finally{
+        _effect.f();
+    }
+});
+const Themed = withTheme(()=>{
+    var _effect = _useSignals();
+    try {
+        return <div/>;
+    } finally{
+        _effect.f();
+    }
+});
+"#
+);
+
+test_inline!(
+    get_syntax(),
+    |tester| as_folder(SignalsTransformVisitor::from_default(
+        tester.comments.clone(),
+        None
+    )),
+    compound_components,
+    // Input codes
+    r#"
+/**
+ * @useSignals
+ */
+export const Menu = Object.assign(MenuRoot, {
+    Item: () => <li>{x.value}</li>,
+    Group: function() {
+        return <ul />
+    },
+    helper() {
+        return 10
+    },
+    [slot]: () => <span />
+})
+/**
+ * @noUseSignals
+ */
+const Tabs = Object.assign(TabsRoot, {
+    Tab: () => <div />
+})
+Menu.Extra = () => 10
+Menu.getLayout = function getLayout(page) {
+    return <Layout>{page}</Layout>
+}
+Menu[slot] = () => <i />
+Tabs.Panel = () => <div />
+"#,
+    // Expected codes
+    r#"
+import { useSignals as _useSignals } from "@preact-signals/safe-react/tracking";
+export const Menu = Object.assign(MenuRoot, {
+    Item: ()=>{
+        var _effect = _useSignals();
+        try {
+            return <li>{x.value}</li>;
+        } finally{
+            _effect.f();
+        }
+    },
+    Group: function() {
+        var _effect = _useSignals();
+        try {
+            return <ul/>;
+        } finally{
+            _effect.f();
+        }
+    },
+    helper () {
+        return 10;
+    },
+    [slot]: ()=>{
+        var _effect = _useSignals();
+        try {
+            return <span/>;
+        } finally{
+            _effect.f();
+        }
+    }
+});
+const Tabs = Object.assign(TabsRoot, {
+    Tab: ()=><div/>
+});
+Menu.Extra = ()=>10;
+Menu.getLayout = function getLayout(page) {
+    return <Layout>{page}</Layout>;
+};
+Menu[slot] = ()=>{
+    var _effect = _useSignals();
+    try {
+        return <i/>;
+    } finally{
+        _effect.f();
+    }
+};
+Tabs.Panel = ()=><div/>;
+"#
+);
+
+test_inline!(
+    get_syntax(),
+    |tester| as_folder(SignalsTransformVisitor::from_default(
+        tester.comments.clone(),
+        None
+    )),
+    factory_components,
+    // Input codes
+    r#"
+function createField(name) {
+    return function Field() {
+        return <input value={store.value} />
+    }
+}
+function createAnonymous() {
+    return function() {
+        return <div />
+    }
+}
+const make = () => () => <div />
+const makeOptIn = () => /** @useSignals */ () => <div />
+const makeMemo = () => memo(function Inner() {
+    return <b />
+})
+function renderList() {
+    return items.map(function Row(i) {
+        return <li />
+    })
+}
+const renderItems = () => items.map(function Item() {
+    return <li />
+})
+class Table extends Component {
+    render() {
+        return this.rows.map(function Row() {
+            return <tr />
+        })
+    }
+}
+"#,
+    // Expected codes
+    r#"
+import { useSignals as _useSignals } from "@preact-signals/safe-react/tracking";
+import { trackClassSignals as _trackClassSignals } from "@preact-signals/safe-react/tracking";
+function createField(name) {
+    return function Field() {
+        var _effect = _useSignals();
+        try {
+            return <input value={store.value}/>;
+        } finally{
+            _effect.f();
+        }
+    };
+}
+function createAnonymous() {
+    return function() {
+        return <div/>;
+    };
+}
+const make = ()=>()=><div/>;
+const makeOptIn = ()=>()=>{
+    var _effect = _useSignals();
+    try {
+        return <div/>;
+    } finally{
+        _effect.f();
+    }
+};
+const makeMemo = ()=>memo(function Inner() {
+    var _effect = _useSignals();
+    try {
+        return <b/>;
+    } finally{
+        _effect.f();
+    }
+});
+function renderList() {
+    return items.map(function Row(i) {
+        return <li/>;
+    });
+}
+const renderItems = ()=>items.map(function Item() {
+    return <li/>;
+});
+class Table extends Component {
+    render() {
+        var _effect = _trackClassSignals(this);
+        try {
+            return this.rows.map(function Row() {
+                return <tr/>;
+            });
+        } finally{
+            _effect.f();
+        }
+    }
+}
+"#
+);
+
+test_inline!(
+    get_syntax(),
+    |tester| as_folder(SignalsTransformVisitor::from_default(
+        tester.comments.clone(),
+        None
+    )),
+    multiple_declarators,
+    // Input codes
+    r#"
+const A = () => <div />, B = () => <span>{s.value}</span>;
+/**
+ * @noUseSignals
+ */
+const C = () => <div />, D = () => <div />;
+const E = () => <div />, /** @noUseSignals */ F = () => <div />, /** @useSignals */ g = () => 10;
+"#,
+    // Expected codes
+    r#"
+import { useSignals as _useSignals } from "@preact-signals/safe-react/tracking";
+const A = ()=>{
+    var _effect = _useSignals();
+    try {
+        return <div/>;
+    } finally{
+        _effect.f();
+    }
+}, B = ()=>{
+    var _effect = _useSignals();
+    try {
+        return <span>{s.value}</span>;
+    } finally{
+        _effect.f();
+    }
+};
+const C = ()=><div/>, D = ()=><div/>;
+const E = ()=>{
+    var _effect = _useSignals();
+    try {
+        return <div/>;
+    } finally{
+        _effect.f();
+    }
+}, F = ()=><div/>, g = ()=>{
+    var _effect = _useSignals();
+    try {
+        return 10;
+    } finally{
+        _effect.f();
+    }
+};
+"#
+);
+
+/// `test_inline` runs fixer, which drops parens around functions wrapped with `as`
+/// or `satisfies` in this swc version, so output is compared before fixer runs
+#[test]
+fn ts_wrappers() {
+    Tester::run(|tester| {
+        let actual = tester.apply_transform(
+            as_folder(SignalsTransformVisitor::from_default(
+                tester.comments.clone(),
+                None,
+            )),
+            "input.js",
+            get_ts_syntax(),
+            r#"
+const A = (() => <div />) as FC
+const B = (function() {
+    return <div />
+}) satisfies Component
+const C = memo((() => <div />) as FC)!
+const D = memo<Props>(() => <div />)
+const E = /** @noUseSignals */ (() => <div />) as FC
+const F = (/** @noUseSignals */ (() => <div />)) satisfies FC
+"#,
+        )?;
+        let expected = tester.apply_transform(
+            noop(),
+            "expected.js",
+            get_ts_syntax(),
+            r#"
+import { useSignals as _useSignals } from "@preact-signals/safe-react/tracking";
+const A = (()=>{
+    var _effect = _useSignals();
+    try {
+        return <div/>;
+    } finally{
+        _effect.f();
+    }
+}) as FC;
+const B = (function() {
+    var _effect = _useSignals();
+    try {
+        return <div/>;
+    } finally{
+        _effect.f();
+    }
+}) satisfies Component;
+const C = memo((()=>{
+    var _effect = _useSignals();
+    try {
+        return <div/>;
+    } finally{
+        _effect.f();
+    }
+}) as FC)!;
+const D = memo<Props>(()=>{
+    var _effect = _useSignals();
+    try {
+        return <div/>;
+    } finally{
+        _effect.f();
+    }
+});
+const E = /** @noUseSignals */ (()=><div/>) as FC;
+const F = (/** @noUseSignals */ (()=><div/>)) satisfies FC;
+"#,
+        )?;
+
+        let comments = tester.comments.clone();
+        assert_eq!(
+            tester.print(&expected, &comments),
+            tester.print(&actual, &comments)
+        );
+        Ok(())
+    });
+}
+
+test_inline!(
+    get_syntax(),
+    |tester| as_folder(SignalsTransformVisitor::from_default(
+        tester.comments.clone(),
+        Some("Component".to_owned())
+    )),
+    commonjs_default_export,
+    // Input codes
+    r#"
+module.exports = () => <div />
+exports.default = function() {
+    return <div />
+}
+module.exports.default = () => <div />
+exports.helper = () => <div />
+"#,
+    // Expected codes
+    r#"
+import { useSignals as _useSignals } from "@preact-signals/safe-react/tracking";
+module.exports = ()=>{
+    var _effect = _useSignals();
+    try {
+        return <div/>;
+    } finally{
+        _effect.f();
+    }
+};
+exports.default = function() {
+    var _effect = _useSignals();
+    try {
+        return <div/>;
+    } finally{
+        _effect.f();
+    }
+};
+module.exports.default = ()=>{
+    var _effect = _useSignals();
+    try {
+        return <div/>;
+    } finally{
+        _effect.f();
+    }
+};
+exports.helper = ()=><div/>;
+"#
+);
+
+test_inline!(
+    get_syntax(),
+    |tester| as_folder(SignalsTransformVisitor::from_default(
+        tester.comments.clone(),
+        get_component_name_from_path(std::path::Path::new("src/Button/index.native.tsx"))
+    )),
+    index_file_default_export,
+    // Input codes
+    r#"
+export default () => <button />
+"#,
+    // Expected codes
+    r#"
+import { useSignals as _useSignals } from "@preact-signals/safe-react/tracking";
+export default (()=>{
+    var _effect = _useSignals();
+    try {
+        return <button/>;
+    } finally{
+        _effect.f();
+    }
+});
+"#
+);
+
+test_inline!(
+    get_syntax(),
+    |tester| as_folder(SignalsTransformVisitor::from_options(
+        serde_json::from_str(r#"{ "hooks": "auto" }"#).unwrap(),
+        tester.comments.clone(),
+        None
+    )),
+    custom_hooks,
+    // Input codes
+    r#"
+function useCartTotal() {
+    return cart.value.reduce((acc, it) => acc + it, 0)
+}
+const useUserName = () => user.value.name
+function useCounter() {
+    return useState(0)
+}
+export default function useCart() {
+    'use strict';
+    return cart.value
+}
+"#,
+    // Expected codes
+    r#"
+import { useHookSignals as _useHookSignals } from "@preact-signals/safe-react/tracking";
+function useCartTotal() {
+    _useHookSignals();
+    return cart.value.reduce((acc, it)=>acc + it, 0);
+}
+const useUserName = ()=>{
+    _useHookSignals();
+    return user.value.name;
+};
+function useCounter() {
+    return useState(0);
+}
+export default function useCart() {
+    'use strict';
+    _useHookSignals();
+    return cart.value;
+}
+"#
+);
+
+test_inline!(
+    get_syntax(),
+    |tester| as_folder(SignalsTransformVisitor::from_options(
+        serde_json::from_str(r#"{ "jsxPragmas": ["React.createElement", "h"] }"#).unwrap(),
+        tester.comments.clone(),
+        None
+    )),
+    precompiled_jsx,
+    // Input codes
+    r#"
+import { jsx as _jsx } from "react/jsx-runtime";
+const A = () => React.createElement("div", null, a.value)
+const B = () => _jsx("div", {})
+const C = () => h("div")
+const D = () => createElement("div")
+"#,
+    // Expected codes
+    r#"
+import { useSignals as _useSignals } from "@preact-signals/safe-react/tracking";
+import { jsx as _jsx } from "react/jsx-runtime";
+const A = ()=>{
+    var _effect = _useSignals();
+    try {
+        return React.createElement("div", null, a.value);
+    } finally{
+        _effect.f();
+    }
+};
+const B = ()=>{
+    var _effect = _useSignals();
+    try {
+        return _jsx("div", {});
+    } finally{
+        _effect.f();
+    }
+};
+const C = ()=>{
+    var _effect = _useSignals();
+    try {
+        return h("div");
+    } finally{
+        _effect.f();
+    }
+};
+const D = ()=>createElement("div");
+"#
+);
+
+test_inline!(
+    get_syntax(),
+    |tester| {
+        use swc_core::{common::chain, ecma::transforms::base::resolver};
+
+        chain!(
+            resolver(Mark::new(), Mark::new(), false),
+            as_folder(AsScript(SignalsTransformVisitor::from_default(
+                tester.comments.clone(),
+                None
+            )))
+        )
+    },
+    precompiled_jsx_script,
+    // Input codes
+    r#"
+const _jsxRuntime = require("react/jsx-runtime");
+const { jsx: _jsx } = require("react/jsx-dev-runtime");
+const A = () => (0, _jsxRuntime.jsx)("div", {})
+const B = () => _jsx("div", {})
+const C = ({ render: _jsx }) => _jsx("div", {})
+"#,
+    // Expected codes
+    r#"
+var _useSignals = require("@preact-signals/safe-react/tracking").useSignals;
+const _jsxRuntime = require("react/jsx-runtime");
+const { jsx: _jsx } = require("react/jsx-dev-runtime");
+const A = ()=>{
+    var _effect = _useSignals();
+    try {
+        return (0, _jsxRuntime.jsx)("div", {});
+    } finally{
+        _effect.f();
+    }
+};
+const B = ()=>{
+    var _effect = _useSignals();
+    try {
+        return _jsx("div", {});
+    } finally{
+        _effect.f();
+    }
+};
+const C = ({ render: _jsx })=>_jsx("div", {});
+"#
+);
+
+test_inline!(
+    get_syntax(),
+    |tester| {
+        use swc_core::{common::chain, ecma::transforms::base::resolver};
+
+        let unresolved_mark = Mark::new();
+        chain!(
+            resolver(unresolved_mark, Mark::new(), false),
+            as_folder(
+                SignalsTransformVisitor::from_options(
+                    serde_json::from_str(r#"{ "mode": "auto" }"#).unwrap(),
+                    tester.comments.clone(),
+                    None
+                )
+                .with_unresolved_mark(unresolved_mark)
+            )
+        )
+    },
+    auto_scope_aware,
+    // Input codes
+    r#"
+const count = signal(0)
+const Counter = () => <div>{count.value}</div>
+const Input = () => {
+    const onChange = (event) => console.log(event.target.value)
+    return <input onChange={onChange} />
+}
+const Ref = () => {
+    const ref = useRef(null)
+    return <div>{ref.current.value}</div>
+}
+const Local = () => {
+    const state = { value: 1 }
+    return <div>{state.value}</div>
+}
+const Unknown = () => <div>{store.value}</div>
+const Prop = ({ item }) => <div>{item.value}</div>
+const State = () => {
+    const [s] = useState(() => signal(0))
+    return <p>{s.value}</p>
+}
+const Pair = () => {
+    const [first] = [count]
+    return <p>{first.value}</p>
+}
+"#,
+    // Expected codes
+    r#"
+import { useSignals as _useSignals } from "@preact-signals/safe-react/tracking";
+const count = signal(0);
+const Counter = ()=>{
+    var _effect = _useSignals();
+    try {
+        return <div>{count.value}</div>;
+    } finally{
+        _effect.f();
+    }
+};
+const Input = ()=>{
+    const onChange = (event)=>console.log(event.target.value);
+    return <input onChange={onChange}/>;
+};
+const Ref = ()=>{
+    const ref = useRef(null);
+    return <div>{ref.current.value}</div>;
+};
+const Local = ()=>{
+    const state = {
+        value: 1
+    };
+    return <div>{state.value}</div>;
+};
+const Unknown = ()=>{
+    var _effect = _useSignals();
+    try {
+        return <div>{store.value}</div>;
+    } finally{
+        _effect.f();
+    }
+};
+const Prop = ({ item })=>{
+    var _effect = _useSignals();
+    try {
+        return <div>{item.value}</div>;
+    } finally{
+        _effect.f();
+    }
+};
+const State = ()=>{
+    var _effect = _useSignals();
+    try {
+        const [s] = useState(()=>signal(0));
+        return <p>{s.value}</p>;
+    } finally{
+        _effect.f();
+    }
+};
+const Pair = ()=>{
+    var _effect = _useSignals();
+    try {
+        const [first] = [
+            count
+        ];
+        return <p>{first.value}</p>;
+    } finally{
+        _effect.f();
+    }
+};
+"#
+);
+
+test_inline!(
+    get_syntax(),
+    |tester| as_folder(SignalsTransformVisitor::from_options(
+        serde_json::from_str(r#"{ "mode": "auto" }"#).unwrap(),
+        tester.comments.clone(),
+        None
+    )),
+    auto_render_phase_reads,
+    // Input codes
+    r#"
+const Button = () => <button onClick={() => count.value++}>+</button>
+const Logger = () => {
+    useEffect(() => {
+        log(x.value)
+    }, [])
+    return <div />
+}
+const Deps = () => {
+    const cb = useCallback(() => x.value, [y.value])
+    return <div onClick={cb} />
+}
+const Render = () => <div title={title.value} onClick={() => count.value++} />
+const Local = () => {
+    const onClick = () => count.value++
+    function onReset() {
+        count.value = 0
+    }
+    return <button onClick={onClick} onDoubleClick={onReset} />
+}
+const Called = () => {
+    const getLabel = () => label.value
+    return <span>{getLabel()}</span>
+}
+"#,
+    // Expected codes
+    r#"
+import { useSignals as _useSignals } from "@preact-signals/safe-react/tracking";
+const Button = ()=><button onClick={()=>count.value++}>+</button>;
+const Logger = ()=>{
+    useEffect(()=>{
+        log(x.value);
+    }, []);
+    return <div/>;
+};
+const Deps = ()=>{
+    var _effect = _useSignals();
+    try {
+        const cb = useCallback(()=>x.value, [
+            y.value
+        ]);
+        return <div onClick={cb}/>;
+    } finally{
+        _effect.f();
+    }
+};
+const Render = ()=>{
+    var _effect = _useSignals();
+    try {
+        return <div title={title.value} onClick={()=>count.value++}/>;
+    } finally{
+        _effect.f();
+    }
+};
+const Local = ()=>{
+    const onClick = ()=>count.value++;
+    function onReset() {
+        count.value = 0;
+    }
+    return <button onClick={onClick} onDoubleClick={onReset}/>;
+};
+const Called = ()=>{
+    var _effect = _useSignals();
+    try {
+        const getLabel = ()=>label.value;
+        return <span>{getLabel()}</span>;
+    } finally{
+        _effect.f();
+    }
+};
+"#
+);
+
+test_inline!(
+    get_syntax(),
+    |tester| as_folder(SignalsTransformVisitor::from_options(
+        serde_json::from_str(r#"{ "mode": "auto" }"#).unwrap(),
+        tester.comments.clone(),
+        None
+    )),
+    auto_local_helpers,
+    // Input codes
+    r#"
+const renderBadge = () => <b>{unread.value}</b>
+function total() {
+    return items.value.length
+}
+function formatTotal() {
+    return `${total()}`
+}
+const Badge = () => <div>{renderBadge()}</div>
+const Total = () => <div>{formatTotal()}</div>
+const Handler = () => <div onClick={() => total()} />
+"#,
+    // Expected codes
+    r#"
+import { useSignals as _useSignals } from "@preact-signals/safe-react/tracking";
+const renderBadge = ()=><b>{unread.value}</b>;
+function total() {
+    return items.value.length;
+}
+function formatTotal() {
+    return `${total()}`;
+}
+const Badge = ()=>{
+    var _effect = _useSignals();
+    try {
+        return <div>{renderBadge()}</div>;
+    } finally{
+        _effect.f();
+    }
+};
+const Total = ()=>{
+    var _effect = _useSignals();
+    try {
+        return <div>{formatTotal()}</div>;
+    } finally{
+        _effect.f();
+    }
+};
+const Handler = ()=><div onClick={()=>total()}/>;
+"#
+);
+
+test_inline!(
+    get_syntax(),
+    |tester| as_folder(SignalsTransformVisitor::from_options(
+        serde_json::from_str(
+            r#"{
+                "mode": "auto",
+                "signalModules": ["@app/state/*", { "./store": ["count", "user"] }]
+            }"#
+        )
+        .unwrap(),
+        tester.comments.clone(),
+        None
+    )),
+    auto_signal_modules,
+    // Input codes
+    r#"
+import { count as total, settings } from "./store";
+import * as store from "./store";
+import { theme } from "@app/state/theme";
+const { user } = store;
+const alias = total;
+const read = (s) => s.value;
+const Count = () => <div>{alias.value}</div>
+const User = () => <div>{user.value.name}</div>
+const Theme = () => <div>{read(theme)}</div>
+const Settings = () => <div>{settings.value}</div>
+const Static = () => <div>{read(1)}</div>
+"#,
+    // Expected codes
+    r#"
+import { useSignals as _useSignals } from "@preact-signals/safe-react/tracking";
+import { count as total, settings } from "./store";
+import * as store from "./store";
+import { theme } from "@app/state/theme";
+const { user } = store;
+const alias = total;
+const read = (s)=>s.value;
+const Count = ()=>{
+    var _effect = _useSignals();
+    try {
+        return <div>{alias.value}</div>;
+    } finally{
+        _effect.f();
+    }
+};
+const User = ()=>{
+    var _effect = _useSignals();
+    try {
+        return <div>{user.value.name}</div>;
+    } finally{
+        _effect.f();
+    }
+};
+const Theme = ()=>{
+    var _effect = _useSignals();
+    try {
+        return <div>{read(theme)}</div>;
+    } finally{
+        _effect.f();
+    }
+};
+const Settings = ()=>{
+    var _effect = _useSignals();
+    try {
+        return <div>{settings.value}</div>;
+    } finally{
+        _effect.f();
+    }
+};
+const Static = ()=><div>{read(1)}</div>;
+"#
+);
+
+test_inline!(
+    get_syntax(),
+    |tester| as_folder(SignalsTransformVisitor::from_options(
+        serde_json::from_str(
+            r#"{
+                "mode": "auto",
+                "autoDetect": {
+                    "properties": ["value", "data"],
+                    "untrackedProperties": ["peek"],
+                    "calls": ["$deref", "useSignalValue", "useDeepSignal"]
+                }
+            }"#
+        )
+        .unwrap(),
+        tester.comments.clone(),
+        None
+    )),
+    auto_detect_accessors,
+    // Input codes
+    r#"
+const Query = () => <div>{useQuery$().data}</div>
+const Deref = () => <div>{$deref(count)}</div>
+const Hook = () => <div>{useSignalValue(count)}</div>
+const Peek = () => <div>{count.peek()}</div>
+"#,
+    // Expected codes
+    r#"
+import { useSignals as _useSignals } from "@preact-signals/safe-react/tracking";
+const Query = ()=>{
+    var _effect = _useSignals();
+    try {
+        return <div>{useQuery$().data}</div>;
+    } finally{
+        _effect.f();
+    }
+};
+const Deref = ()=>{
+    var _effect = _useSignals();
+    try {
+        return <div>{$deref(count)}</div>;
+    } finally{
+        _effect.f();
+    }
+};
+const Hook = ()=>{
+    var _effect = _useSignals();
+    try {
+        return <div>{useSignalValue(count)}</div>;
+    } finally{
+        _effect.f();
+    }
+};
+const Peek = ()=><div>{count.peek()}</div>;
+"#
+);
+
+test_inline!(
+    get_syntax(),
+    |tester| as_folder(SignalsTransformVisitor::from_options(
+        serde_json::from_str(r#"{ "mode": "auto" }"#).unwrap(),
+        tester.comments.clone(),
+        None
+    )),
+    auto_destructured_reads,
+    // Input codes
+    r#"
+const Count = () => {
+    const { value } = count;
+    return <div>{value}</div>;
+}
+const User = () => {
+    const { value: user = null } = useUser();
+    return <div>{user.name}</div>;
+}
+const Nested = () => {
+    const { settings: { value: settings } } = store;
+    return <div>{settings.theme}</div>;
+}
+const Item = ({ value }) => <li>{value}</li>
+const Plain = () => {
+    const { name } = props;
+    return <div>{name}</div>;
+}
+"#,
+    // Expected codes
+    r#"
+import { useSignals as _useSignals } from "@preact-signals/safe-react/tracking";
+const Count = ()=>{
+    var _effect = _useSignals();
+    try {
+        const { value } = count;
+        return <div>{value}</div>;
+    } finally{
+        _effect.f();
+    }
+};
+const User = ()=>{
+    var _effect = _useSignals();
+    try {
+        const { value: user = null } = useUser();
+        return <div>{user.name}</div>;
+    } finally{
+        _effect.f();
+    }
+};
+const Nested = ()=>{
+    var _effect = _useSignals();
+    try {
+        const { settings: { value: settings } } = store;
+        return <div>{settings.theme}</div>;
+    } finally{
+        _effect.f();
+    }
+};
+const Item = ({ value })=>{
+    var _effect = _useSignals();
+    try {
+        return <li>{value}</li>;
+    } finally{
+        _effect.f();
+    }
+};
+const Plain = ()=>{
+    const { name } = props;
+    return <div>{name}</div>;
+};
+"#
+);
+
+test_inline!(
+    get_syntax(),
+    |tester| as_folder(SignalsTransformVisitor::from_options(
+        serde_json::from_str(r#"{ "mode": "auto" }"#).unwrap(),
+        tester.comments.clone(),
+        None
+    )),
+    auto_nested_param_destructuring,
+    // Input codes
+    r#"
+const Select = ({ options }) => (
+    <select>
+        {options.map(({ label, value }) => <option value={value}>{label}</option>)}
+    </select>
+)
+const Field = () => <Controller render={({ value, onChange }) => <input value={value} onChange={onChange} />} />
+function List({ items }) {
+    return items.map(function({ value }) {
+        return <li>{value}</li>
+    })
+}
+"#,
+    // Expected codes
+    r#"
+const Select = ({ options })=><select>
+        {options.map(({ label, value })=><option value={value}>{label}</option>)}
+    </select>;
+const Field = ()=><Controller render={({ value, onChange })=><input value={value} onChange={onChange}/>}/>;
+function List({ items }) {
+    return items.map(function({ value }) {
+        return <li>{value}</li>;
+    });
+}
+"#
+);
+
+test_inline!(
+    get_syntax(),
+    |tester| as_folder(SignalsTransformVisitor::from_default(
+        tester.comments.clone(),
+        None
+    )),
+    branching_components,
+    // Input codes
+    r#"
+const View = isMobile ? () => <Mobile /> : () => <Desktop />
+const Fallback = Custom ?? (() => <Spinner />)
+const Memoized = (0, memo)(() => <div />)
+"#,
+    // Expected codes
+    r#"
+import { useSignals as _useSignals } from "@preact-signals/safe-react/tracking";
+const View = isMobile ? ()=>{
+    var _effect = _useSignals();
+    try {
+        return <Mobile/>;
+    } finally{
+        _effect.f();
+    }
+} : ()=>{
+    var _effect = _useSignals();
+    try {
+        return <Desktop/>;
+    } finally{
+        _effect.f();
+    }
+};
+const Fallback = Custom ?? (()=>{
+    var _effect = _useSignals();
+    try {
+        return <Spinner/>;
+    } finally{
+        _effect.f();
+    }
+});
+const Memoized = (0, memo)(()=>{
+    var _effect = _useSignals();
+    try {
+        return <div/>;
+    } finally{
+        _effect.f();
+    }
+});
+"#
+);
+
+test_inline!(
+    get_syntax(),
+    |tester| as_folder(SignalsTransformVisitor::from_options(
+        serde_json::from_str(r#"{ "mode": "manual" }"#).unwrap(),
+        tester.comments.clone(),
+        None
+    )),
+    hoc_inner_directives,
+    // Input codes
+    r#"
+const Foo = memo(/** @useSignals */ () => <div />)
+const Bar = memo(/** @useSignals */ forwardRef((props, ref) => <div ref={ref} />))
+"#,
+    // Expected codes
+    r#"
+import { useSignals as _useSignals } from "@preact-signals/safe-react/tracking";
+const Foo = memo(/** @useSignals */ ()=>{
+    var _effect = _useSignals();
+    try {
+        return <div/>;
+    } finally{
+        _effect.f();
+    }
+});
+const Bar = memo(/** @useSignals */ forwardRef((props, ref)=>{
+    var _effect = _useSignals();
+    try {
+        return <div ref={ref}/>;
+    } finally{
+        _effect.f();
+    }
+}));
+"#
+);
+
+test_inline!(
+    get_syntax(),
+    |tester| as_folder(SignalsTransformVisitor::from_default(
+        tester.comments.clone(),
+        None
+    )),
+    hoc_inner_opt_out,
+    // Input codes
+    r#"
+export default forwardRef(/** @noUseSignals */ function Inner(props, ref) {
+    return <div ref={ref} />;
+})
+"#,
+    // Expected codes
+    r#"
+export default forwardRef(/** @noUseSignals */ function Inner(props, ref) {
+    return <div ref={ref}/>;
+});
+"#
+);
+
+test_inline!(
+    get_syntax(),
+    |tester| as_folder(SignalsTransformVisitor::from_default(
+        tester.comments.clone(),
+        None
+    )),
+    file_opt_out,
+    // Input codes
+    r#"
+/** @noUseSignals */
+import { signal } from "@preact/signals-react";
+const A = () => <div>{a.value}</div>
+/** @useSignals */
+const B = () => <div>{b.value}</div>
+"#,
+    // Expected codes
+    r#"
+import { useSignals as _useSignals } from "@preact-signals/safe-react/tracking";
+/** @noUseSignals */ import { signal } from "@preact/signals-react";
+const A = ()=><div>{a.value}</div>;
+/** @useSignals */ const B = ()=>{
+    var _effect = _useSignals();
+    try {
+        return <div>{b.value}</div>;
+    } finally{
+        _effect.f();
+    }
+};
+"#
+);
+
+test_inline!(
+    get_syntax(),
+    |tester| as_folder(SignalsTransformVisitor::from_default(
+        tester.comments.clone(),
+        None
+    )),
+    file_opt_out_import_only,
+    // Input codes
+    r#"
+/** @noUseSignals */
+const config = {};
+import { signal } from "@preact/signals-react";
+const A = () => <div>{a.value}</div>
+"#,
+    // Expected codes
+    r#"
+import { useSignals as _useSignals } from "@preact-signals/safe-react/tracking";
+/** @noUseSignals */ const config = {};
+import { signal } from "@preact/signals-react";
+const A = ()=>{
+    var _effect = _useSignals();
+    try {
+        return <div>{a.value}</div>;
+    } finally{
+        _effect.f();
+    }
+};
+"#
+);
+
+test_inline!(
+    get_syntax(),
+    |tester| as_folder(AsScript(SignalsTransformVisitor::from_default(
+        tester.comments.clone(),
+        None
+    ))),
+    script_file_opt_out,
+    // Input codes
+    r#"
+"use strict";
+/** @noUseSignals */
+const { signal } = require("@preact/signals-react");
+const A = () => <div>{a.value}</div>
+/** @useSignals */
+const B = () => <div>{b.value}</div>
+"#,
+    // Expected codes
+    r#"
+"use strict";
+var _useSignals = require("@preact-signals/safe-react/tracking").useSignals;
+/** @noUseSignals */ const { signal } = require("@preact/signals-react");
+const A = ()=><div>{a.value}</div>;
+/** @useSignals */ const B = ()=>{
+    var _effect = _useSignals();
+    try {
+        return <div>{b.value}</div>;
+    } finally{
+        _effect.f();
+    }
+};
+"#
+);
+
+test_inline!(
+    get_syntax(),
+    |tester| as_folder(SignalsTransformVisitor::from_default(
+        tester.comments.clone(),
+        None
+    )),
+    file_mode_directive,
+    // Input codes
+    r#"
+"use signals auto";
+const A = () => <div>{a.value}</div>
+const B = () => <div />
+"#,
+    // Expected codes
+    r#"
+"use signals auto";
+import { useSignals as _useSignals } from "@preact-signals/safe-react/tracking";
+const A = ()=>{
+    var _effect = _useSignals();
+    try {
+        return <div>{a.value}</div>;
+    } finally{
+        _effect.f();
+    }
+};
+const B = ()=><div/>;
+"#
+);
+
+test_inline!(
+    get_syntax(),
+    |tester| as_folder(SignalsTransformVisitor::from_default(
+        tester.comments.clone(),
+        None
+    )),
+    function_directives,
+    // Input codes
+    r#"
+function MyComponent() {
+    "use no signals";
+    return <div>{signal.value}</div>;
+}
+const render = () => {
+    "use signals";
+    return <div>{signal.value}</div>;
+}
+"#,
+    // Expected codes
+    r#"
+import { useSignals as _useSignals } from "@preact-signals/safe-react/tracking";
+function MyComponent() {
+    return <div>{signal.value}</div>;
+}
+const render = ()=>{
+    var _effect = _useSignals();
+    try {
+        return <div>{signal.value}</div>;
+    } finally{
+        _effect.f();
+    }
+};
+"#
+);
+
+test_inline!(
+    get_syntax(),
+    |tester| as_folder(SignalsTransformVisitor::from_options(
+        serde_json::from_str::<PreactSignalsPluginOptions>(
+            r#"{
+                "mode": "auto",
+                "overrides": [
+                    { "files": ["legacy/**"], "mode": "all" },
+                    { "files": ["**/vendored/**"], "importSource": "./runtime" },
+                    { "files": ["legacy/vendored/**"], "mode": "manual" },
+                    { "files": ["legacy/**"], "importSource": "@legacy/runtime" }
+                ]
+            }"#
+        )
+        .unwrap()
+        .resolve(Some(&get_relative_path(
+            "/app/legacy/src/Button.tsx",
+            Some("/app")
+        ))),
+        tester.comments.clone(),
+        None
+    )),
+    options_overrides,
+    // Input codes
+    r#"
+const Button = () => <button />
+"#,
+    // Expected codes
+    r#"
+import { useSignals as _useSignals } from "@legacy/runtime";
+const Button = ()=>{
+    var _effect = _useSignals();
+    try {
+        return <button/>;
+    } finally{
+        _effect.f();
+    }
+};
+"#
+);
+
+#[test]
+fn path_filter() {
+    let include = ["src/**".to_owned()];
+    let exclude = [
+        "**/node_modules/**".to_owned(),
+        "**/*.stories.tsx".to_owned(),
+    ];
+    let filter = PathFilter::new(Some(&include[..]), Some(&exclude[..]));
+    let allows = |filename: &str| filter.allows(&get_relative_path(filename, Some("/app")));
+
+    assert!(allows("/app/src/Button.tsx"));
+    assert!(!allows("/app/src/Button.stories.tsx"));
+    assert!(!allows("/app/src/node_modules/ui/Button.tsx"));
+    assert!(!allows("/app/test/Button.tsx"));
+    assert!(!allows("/other/src/Button.tsx"));
+    assert!(PathFilter::new(None, None).allows("test/Button.tsx"));
+}
+
+#[test]
+fn relative_path() {
+    assert_eq!(
+        get_relative_path("/app/src/a.tsx", Some("/app")),
+        "src/a.tsx"
+    );
+    assert_eq!(
+        get_relative_path("/app/src/a.tsx", Some("/app/")),
+        "src/a.tsx"
+    );
+    assert_eq!(
+        get_relative_path("/application/a.tsx", Some("/app")),
+        "/application/a.tsx"
+    );
+    assert_eq!(
+        get_relative_path("C:\\app\\src\\a.tsx", Some("C:\\app")),
+        "src/a.tsx"
+    );
+    assert_eq!(get_relative_path("src/a.tsx", None), "src/a.tsx");
+}
@@ -1,10 +1,16 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use regex::Regex;
+use serde::Deserialize;
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
 use swc_core::{
-    common::{sync::Lazy, Span, DUMMY_SP},
+    common::{sync::Lazy, Mark, Span, DUMMY_SP},
     ecma::{
         ast::*,
         atoms::Atom,
-        utils::private_ident,
+        utils::{find_pat_ids, private_ident},
         visit::{Visit, VisitWith},
     },
 };
@@ -39,14 +45,100 @@ fn is_component_name(name: &str) -> bool {
     RE.is_match(name)
 }
 
+/// Unwraps parens and TypeScript wrappers, which do not affect runtime value:
+/// `(() => <div />) as FC`, `memo(Component)!`
+pub trait UnwrapTransparent {
+    fn unwrap_transparent(&self) -> &Expr;
+    fn unwrap_transparent_mut(&mut self) -> &mut Expr;
+    /// Spans of expression, of every wrapper and of unwrapped child, a comment can be
+    /// placed before any of them: `/** @noUseSignals */ (() => <div />) as FC`
+    fn get_transparent_spans(&self) -> Vec<Option<Span>>;
+}
+
+fn get_transparent_child(expr: &Expr) -> Option<&Expr> {
+    match expr {
+        Expr::Paren(ParenExpr { expr, .. })
+        | Expr::TsAs(TsAsExpr { expr, .. })
+        | Expr::TsSatisfies(TsSatisfiesExpr { expr, .. })
+        | Expr::TsTypeAssertion(TsTypeAssertion { expr, .. })
+        | Expr::TsConstAssertion(TsConstAssertion { expr, .. })
+        | Expr::TsNonNull(TsNonNullExpr { expr, .. })
+        | Expr::TsInstantiation(TsInstantiation { expr, .. }) => Some(expr),
+        _ => None,
+    }
+}
+
+impl UnwrapTransparent for Expr {
+    fn unwrap_transparent(&self) -> &Expr {
+        match get_transparent_child(self) {
+            Some(expr) => expr.unwrap_transparent(),
+            None => self,
+        }
+    }
+    fn unwrap_transparent_mut(&mut self) -> &mut Expr {
+        match self {
+            Expr::Paren(ParenExpr { expr, .. })
+            | Expr::TsAs(TsAsExpr { expr, .. })
+            | Expr::TsSatisfies(TsSatisfiesExpr { expr, .. })
+            | Expr::TsTypeAssertion(TsTypeAssertion { expr, .. })
+            | Expr::TsConstAssertion(TsConstAssertion { expr, .. })
+            | Expr::TsNonNull(TsNonNullExpr { expr, .. })
+            | Expr::TsInstantiation(TsInstantiation { expr, .. }) => expr.unwrap_transparent_mut(),
+            _ => self,
+        }
+    }
+    fn get_transparent_spans(&self) -> Vec<Option<Span>> {
+        let mut spans = vec![Some(*self.get_span())];
+        if let Some(expr) = get_transparent_child(self) {
+            spans.extend(expr.get_transparent_spans());
+        }
+        spans
+    }
+}
+
+/// Infers component name from file path, all extensions are stripped and
+/// `index` files are named by their directory:
+/// `Button.native.tsx`, `Button/index.tsx` -> `Button`
+pub fn get_component_name_from_path(path: &Path) -> Option<String> {
+    fn get_stem(path: &Path) -> Option<&str> {
+        path.file_name()?.to_str()?.split('.').next()
+    }
+
+    match get_stem(path)? {
+        "index" => get_stem(path.parent()?).map(|it| it.to_owned()),
+        name => Some(name.to_owned()),
+    }
+}
+
+fn is_hook_name(name: &str) -> bool {
+    static RE: Lazy<Regex> = Lazy::new(|| Regex::new("^use[A-Z]").unwrap());
+    RE.is_match(name)
+}
+
+/// Computed key, which is known at compile time: `{ ["Item"]: value }`
+fn is_static_name(expr: &Expr) -> bool {
+    matches!(expr.unwrap_parens(), Expr::Lit(Lit::Str(_)))
+}
+
 pub trait MaybeComponentName {
     fn is_component_name(&self) -> bool;
+    /// custom hooks are named like `useSomething`
+    fn is_hook_name(&self) -> bool {
+        false
+    }
+    /// name is known only at runtime: `{ [key]: value }`, `Menu[key] = value`
+    fn is_dynamic_name(&self) -> bool {
+        false
+    }
 }
 
 impl MaybeComponentName for str {
     fn is_component_name(&self) -> bool {
         is_component_name(self)
     }
+    fn is_hook_name(&self) -> bool {
+        is_hook_name(self)
+    }
 }
 
 impl MaybeComponentName for Str {
@@ -58,11 +150,17 @@ impl MaybeComponentName for Ident {
     fn is_component_name(&self) -> bool {
         is_component_name(self.sym.as_str())
     }
+    fn is_hook_name(&self) -> bool {
+        is_hook_name(self.sym.as_str())
+    }
 }
 impl MaybeComponentName for BindingIdent {
     fn is_component_name(&self) -> bool {
         self.id.is_component_name()
     }
+    fn is_hook_name(&self) -> bool {
+        self.id.is_hook_name()
+    }
 }
 impl MaybeComponentName for Pat {
     fn is_component_name(&self) -> bool {
@@ -71,6 +169,12 @@ impl MaybeComponentName for Pat {
         }
         false
     }
+    fn is_hook_name(&self) -> bool {
+        if let Pat::Ident(id) = self {
+            return id.is_hook_name();
+        }
+        false
+    }
 }
 
 // this crazy stuff is not supported in babel
@@ -109,6 +213,14 @@ impl MaybeComponentName for MemberProp {
             }
         }
     }
+    fn is_dynamic_name(&self) -> bool {
+        matches!(self, MemberProp::Computed(ComputedPropName { expr, .. }) if !is_static_name(expr))
+    }
+}
+impl MaybeComponentName for PrivateName {
+    fn is_component_name(&self) -> bool {
+        false
+    }
 }
 impl MaybeComponentName for Expr {
     fn is_component_name(&self) -> bool {
@@ -129,6 +241,9 @@ impl MaybeComponentName for PropName {
             _ => false,
         }
     }
+    fn is_dynamic_name(&self) -> bool {
+        matches!(self, PropName::Computed(ComputedPropName { expr, .. }) if !is_static_name(expr))
+    }
 }
 
 impl MaybeComponentName for AssignTarget {
@@ -145,13 +260,69 @@ impl MaybeComponentName for AssignTarget {
             _ => false,
         }
     }
+    fn is_dynamic_name(&self) -> bool {
+        match self {
+            AssignTarget::Simple(SimpleAssignTarget::Member(member)) => {
+                member.prop.is_dynamic_name()
+            }
+            _ => false,
+        }
+    }
 }
 
 pub enum FunctionLike<'a> {
     Arrow(&'a mut ArrowExpr),
     Fn(&'a mut FnExpr),
 }
-pub fn wrap_with_use_signals(n: &Vec<Stmt>, use_signals_ident: Ident) -> Vec<Stmt> {
+pub fn wrap_with_use_signals(n: &[Stmt], use_signals_ident: Ident) -> Vec<Stmt> {
+    wrap_with_tracking(
+        n,
+        CallExpr {
+            span: DUMMY_SP,
+            callee: Callee::Expr(Box::new(Expr::Ident(use_signals_ident))),
+            args: vec![],
+            type_args: None,
+        },
+    )
+}
+
+/// Wraps class component `render` body, tracking is started with `trackClassSignals(this)`
+pub fn wrap_with_track_class_signals(n: &[Stmt], track_class_signals_ident: Ident) -> Vec<Stmt> {
+    wrap_with_tracking(
+        n,
+        CallExpr {
+            span: DUMMY_SP,
+            callee: Callee::Expr(Box::new(Expr::Ident(track_class_signals_ident))),
+            args: vec![ExprOrSpread {
+                spread: None,
+                expr: Box::new(Expr::This(ThisExpr { span: DUMMY_SP })),
+            }],
+            type_args: None,
+        },
+    )
+}
+
+/// Starts hook body with `useHookSignals()` after directive prologue, hooks aren't
+/// wrapped with `try/finally`, since they run inside of component render
+pub fn with_hook_signals(n: &[Stmt], use_hook_signals_ident: Ident) -> Vec<Stmt> {
+    let mut stmts = without_signals_directives(n);
+    let directives_count = stmts.iter().map_while(get_directive).count();
+    stmts.insert(
+        directives_count,
+        Stmt::Expr(ExprStmt {
+            span: DUMMY_SP,
+            expr: Box::new(Expr::Call(CallExpr {
+                span: DUMMY_SP,
+                callee: Callee::Expr(Box::new(Expr::Ident(use_hook_signals_ident))),
+                args: vec![],
+                type_args: None,
+            })),
+        }),
+    );
+    stmts
+}
+
+fn wrap_with_tracking(n: &[Stmt], start_tracking: CallExpr) -> Vec<Stmt> {
     let mut new_stmts = Vec::new();
     let signal_effect_ident = private_ident!("_effect");
     new_stmts.push(Stmt::Decl(Decl::Var(Box::new(VarDecl {
@@ -161,12 +332,7 @@ pub fn wrap_with_use_signals(n: &Vec<Stmt>, use_signals_ident: Ident) -> Vec<Stm
         decls: vec![VarDeclarator {
             definite: false,
             span: DUMMY_SP,
-            init: Some(Box::new(Expr::Call(CallExpr {
-                span: DUMMY_SP,
-                callee: Callee::Expr(Box::new(Expr::Ident(use_signals_ident))),
-                args: vec![],
-                type_args: None,
-            }))),
+            init: Some(Box::new(Expr::Call(start_tracking))),
             name: Pat::Ident(BindingIdent {
                 id: signal_effect_ident.clone(),
                 type_ann: None,
@@ -177,7 +343,7 @@ pub fn wrap_with_use_signals(n: &Vec<Stmt>, use_signals_ident: Ident) -> Vec<Stm
         span: DUMMY_SP,
         block: BlockStmt {
             span: DUMMY_SP,
-            stmts: n.to_vec(),
+            stmts: without_signals_directives(n),
         },
         handler: None,
         finalizer: Some(BlockStmt {
@@ -205,8 +371,38 @@ pub fn wrap_with_use_signals(n: &Vec<Stmt>, use_signals_ident: Ident) -> Vec<Stm
     new_stmts
 }
 
+fn is_signals_directive(directive: &str) -> bool {
+    matches!(directive, "use signals" | "use no signals")
+}
+
+/// Removes `"use signals"` and `"use no signals"` from directive prologue
+pub fn remove_signals_directives(stmts: &mut Vec<Stmt>) {
+    while let Some(index) = stmts
+        .iter()
+        .map_while(get_directive)
+        .position(is_signals_directive)
+    {
+        stmts.remove(index);
+    }
+}
+
+fn without_signals_directives(n: &[Stmt]) -> Vec<Stmt> {
+    let mut stmts = n.to_vec();
+    remove_signals_directives(&mut stmts);
+    stmts
+}
+
 pub trait SignalWrappable {
-    fn wrap_with_use_signals(&mut self, import_use_signals: Ident);
+    fn wrap_body<F>(&mut self, wrap: F)
+    where
+        F: FnOnce(&[Stmt]) -> Vec<Stmt>;
+
+    fn wrap_with_use_signals(&mut self, import_use_signals: Ident) {
+        self.wrap_body(|stmts| wrap_with_use_signals(stmts, import_use_signals))
+    }
+    fn start_with_hook_signals(&mut self, import_use_hook_signals: Ident) {
+        self.wrap_body(|stmts| with_hook_signals(stmts, import_use_hook_signals))
+    }
 }
 
 impl<'a> FunctionLike<'a> {
@@ -222,58 +418,393 @@ impl<'a> FunctionLike<'a> {
 }
 
 impl SignalWrappable for Function {
-    fn wrap_with_use_signals(&mut self, import_use_signals: Ident) {
+    fn wrap_body<F>(&mut self, wrap: F)
+    where
+        F: FnOnce(&[Stmt]) -> Vec<Stmt>,
+    {
         if let Some(body) = &mut self.body {
-            body.stmts = wrap_with_use_signals(&body.stmts, import_use_signals);
+            body.stmts = wrap(&body.stmts);
         }
     }
 }
 impl SignalWrappable for FnExpr {
-    fn wrap_with_use_signals(&mut self, import_use_signals: Ident) {
-        self.function.wrap_with_use_signals(import_use_signals);
+    fn wrap_body<F>(&mut self, wrap: F)
+    where
+        F: FnOnce(&[Stmt]) -> Vec<Stmt>,
+    {
+        self.function.wrap_body(wrap);
     }
 }
 impl SignalWrappable for ArrowExpr {
-    fn wrap_with_use_signals(&mut self, import_use_signals: Ident) {
+    fn wrap_body<F>(&mut self, wrap: F)
+    where
+        F: FnOnce(&[Stmt]) -> Vec<Stmt>,
+    {
         let mut block = self.body.to_block();
-        let wrapped_body = wrap_with_use_signals(&block.stmts, import_use_signals);
+        let wrapped_body = wrap(&block.stmts);
         block.stmts = wrapped_body;
         self.body = Box::new(BlockStmtOrExpr::BlockStmt(block.to_owned()));
     }
 }
 impl<'a> SignalWrappable for FunctionLike<'a> {
-    fn wrap_with_use_signals(&mut self, import_use_signals: Ident) {
+    fn wrap_body<F>(&mut self, wrap: F)
+    where
+        F: FnOnce(&[Stmt]) -> Vec<Stmt>,
+    {
         match self {
-            FunctionLike::Arrow(arrow_expr) => arrow_expr.wrap_with_use_signals(import_use_signals),
-            FunctionLike::Fn(fn_expr) => fn_expr.wrap_with_use_signals(import_use_signals),
+            FunctionLike::Arrow(arrow_expr) => arrow_expr.wrap_body(wrap),
+            FunctionLike::Fn(fn_expr) => fn_expr.wrap_body(wrap),
         }
     }
 }
 
-pub fn extract_fn_from_expr<'a>(expr: &'a mut Expr) -> Option<FunctionLike<'a>> {
-    match expr {
-        Expr::Fn(fn_expr) if fn_expr.is_regular() => Some(FunctionLike::Fn(fn_expr)),
-        Expr::Arrow(arrow_expr) if arrow_expr.is_regular() => Some(FunctionLike::Arrow(arrow_expr)),
+/// Decides which calls are treated as HOCs in `extract_fns_from_expr`.
+/// In non strict mode every call is considered to be a HOC
+pub struct HocFilter {
+    names: Vec<String>,
+    strict: bool,
+}
+
+impl HocFilter {
+    pub fn new(names: Option<Vec<String>>, strict: bool) -> Self {
+        HocFilter {
+            names: names.unwrap_or_else(get_default_hocs),
+            strict,
+        }
+    }
+
+    /// Same filter, which allows only listed HOCs
+    pub fn to_strict(&self) -> Self {
+        HocFilter {
+            names: self.names.clone(),
+            strict: true,
+        }
+    }
+
+    fn allows(&self, callee: &Callee) -> bool {
+        if !self.strict {
+            return true;
+        }
+        match callee {
+            Callee::Expr(expr) => get_callee_name(expr)
+                .map(|name| self.names.iter().any(|it| it.as_str() == name.as_str()))
+                .unwrap_or(false),
+            _ => false,
+        }
+    }
+}
+
+impl Default for HocFilter {
+    fn default() -> Self {
+        HocFilter::new(None, false)
+    }
+}
+
+fn get_default_hocs() -> Vec<String> {
+    [
+        "memo",
+        "forwardRef",
+        "React.memo",
+        "React.forwardRef",
+        "lazy",
+    ]
+    .iter()
+    .map(|it| it.to_string())
+    .collect()
+}
+
+/// Dotted name of callee: `memo`, `React.memo`. Curried calls are named
+/// by their innermost callee: `connect(mapState)` -> `connect`
+fn get_callee_name(expr: &Expr) -> Option<String> {
+    match expr.unwrap_transparent() {
+        Expr::Ident(ident) => Some(ident.sym.to_string()),
+        Expr::Member(MemberExpr {
+            obj,
+            prop: MemberProp::Ident(prop),
+            span: _,
+        }) => get_callee_name(obj).map(|obj| format!("{}.{}", obj, prop.sym)),
+        Expr::Call(CallExpr {
+            callee: Callee::Expr(callee),
+            ..
+        }) => get_callee_name(callee),
+        // `(0, memo)`
+        Expr::Seq(SeqExpr { exprs, .. }) => exprs.last().and_then(|it| get_callee_name(it)),
+        _ => None,
+    }
+}
+
+/// Extracted component functions with comment spans along the HOC chain
+pub type ExtractedFns<'a> = Vec<(FunctionLike<'a>, Vec<Option<Span>>)>;
+
+fn with_span(mut components: ExtractedFns, span: Span) -> ExtractedFns {
+    for (_, spans) in components.iter_mut() {
+        spans.push(Some(span));
+    }
+    components
+}
+
+/// Reference to existing component: `Inner`, `ui.Inner`
+fn is_component_ref(expr: &Expr) -> bool {
+    match expr.unwrap_transparent() {
+        expr @ (Expr::Ident(_) | Expr::Member(_)) => expr.is_component_name(),
+        _ => false,
+    }
+}
+
+/// Extracts component functions from expression. Calls are treated as HOCs:
+/// arguments of the outermost call are scanned left to right and the first
+/// argument that yields functions wins, so `observer(options, () => {})` works.
+/// Call wrapping existing component (`memo(Inner, areEqual)`) yields nothing.
+/// If there is no such argument and callee is a call itself, curried chain
+/// is followed. Only calls allowed by `hocs` are descended into. Every branch
+/// of conditional and logical expressions is yielded, together with spans of
+/// nested arguments and of the function itself, which may have directives
+pub fn extract_fns_from_expr<'a>(expr: &'a mut Expr, hocs: &HocFilter) -> ExtractedFns<'a> {
+    match expr.unwrap_transparent_mut() {
+        Expr::Fn(fn_expr) if fn_expr.is_regular() => {
+            let span = fn_expr.function.span;
+            vec![(FunctionLike::Fn(fn_expr), vec![Some(span)])]
+        }
+        Expr::Arrow(arrow_expr) if arrow_expr.is_regular() => {
+            let span = arrow_expr.span;
+            vec![(FunctionLike::Arrow(arrow_expr), vec![Some(span)])]
+        }
         Expr::Call(CallExpr {
             args,
             span: _,
             type_args: _,
-            callee: _,
-        }) => {
-            if let Some(ExprOrSpread {
-                spread: None,
-                expr: first_arg_expr,
-            }) = args.as_mut_slice().take_first_mut()
+            callee,
+        }) if hocs.allows(callee) => {
+            // `memo(Inner, (prev, next) => prev.id === next.id)` wraps existing
+            // component, its comparator isn't a component
+            if let Some(ExprOrSpread { spread: None, expr }) = args.first()
+                && is_component_ref(expr)
             {
-                extract_fn_from_expr(first_arg_expr.unwrap_parens_mut())
-            } else {
-                None
+                return vec![];
+            }
+            for arg in args.iter_mut().filter(|it| it.spread.is_none()) {
+                // `memo(/** @useSignals */ () => {})`
+                let span = *arg.expr.get_span();
+                let components = extract_fns_from_expr(arg.expr.unwrap_transparent_mut(), hocs);
+                if !components.is_empty() {
+                    return with_span(components, span);
+                }
             }
+            match callee {
+                Callee::Expr(callee_expr)
+                    if let Expr::Call(_) = callee_expr.unwrap_transparent() =>
+                {
+                    extract_fns_from_expr(callee_expr.unwrap_transparent_mut(), hocs)
+                }
+                _ => vec![],
+            }
+        }
+        // `isMobile ? () => <Mobile /> : () => <Desktop />`
+        Expr::Cond(CondExpr { cons, alt, .. }) => {
+            let (cons_span, alt_span) = (*cons.get_span(), *alt.get_span());
+            let mut components = with_span(extract_fns_from_expr(cons, hocs), cons_span);
+            components.extend(with_span(extract_fns_from_expr(alt, hocs), alt_span));
+            components
         }
+        // `Custom ?? (() => <Spinner />)`
+        Expr::Bin(BinExpr {
+            op: BinaryOp::LogicalOr | BinaryOp::LogicalAnd | BinaryOp::NullishCoalescing,
+            left,
+            right,
+            ..
+        }) => {
+            let (left_span, right_span) = (*left.get_span(), *right.get_span());
+            let mut components = with_span(extract_fns_from_expr(left, hocs), left_span);
+            components.extend(with_span(extract_fns_from_expr(right, hocs), right_span));
+            components
+        }
+        Expr::Seq(SeqExpr { exprs, .. }) => match exprs.last_mut() {
+            Some(expr) => extract_fns_from_expr(expr, hocs),
+            None => vec![],
+        },
+        _ => vec![],
+    }
+}
+
+/// String of directive prologue statement: `"use strict"`, `"use no signals"`
+pub fn get_directive(stmt: &Stmt) -> Option<&str> {
+    match stmt {
+        Stmt::Expr(ExprStmt {
+            expr: box Expr::Lit(Lit::Str(str)),
+            ..
+        }) => Some(str.value.as_str()),
+        _ => None,
+    }
+}
+
+pub fn get_module_item_directive(item: &ModuleItem) -> Option<&str> {
+    match item {
+        ModuleItem::Stmt(stmt) => get_directive(stmt),
         _ => None,
     }
 }
 
+fn is_render_key(key: &PropName) -> bool {
+    match key {
+        PropName::Ident(ident) => ident.sym.as_str() == "render",
+        PropName::Str(str) => str.value.as_str() == "render",
+        _ => false,
+    }
+}
+
+/// Finds `render` method of class component
+pub fn find_render_method(class: &mut Class) -> Option<&mut ClassMethod> {
+    class.body.iter_mut().find_map(|member| match member {
+        ClassMember::Method(method)
+            if !method.is_static
+                && method.kind == MethodKind::Method
+                && is_render_key(&method.key)
+                && method.function.is_regular() =>
+        {
+            Some(method)
+        }
+        _ => None,
+    })
+}
+
+fn is_object_assign(callee: &Callee) -> bool {
+    match callee {
+        Callee::Expr(expr) => match expr.unwrap_transparent() {
+            Expr::Member(MemberExpr {
+                obj,
+                prop: MemberProp::Ident(prop),
+                span: _,
+            }) => {
+                prop.sym.as_str() == "assign"
+                    && matches!(obj.unwrap_transparent(), Expr::Ident(obj) if obj.sym.as_str() == "Object")
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Spans of object literals which are merged into compound component:
+/// `Object.assign(Root, { Item: () => <li /> })`
+pub fn get_compound_object_spans(expr: &Expr) -> Vec<Span> {
+    match expr.unwrap_transparent() {
+        Expr::Call(CallExpr {
+            callee,
+            args,
+            span: _,
+            type_args: _,
+        }) if is_object_assign(callee) => args
+            .iter()
+            .filter_map(|it| match it {
+                ExprOrSpread { spread: None, expr }
+                    if let Expr::Object(object) = expr.unwrap_transparent() =>
+                {
+                    Some(object.span)
+                }
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+fn get_member_root_ident(expr: &Expr) -> Option<&Ident> {
+    match expr.unwrap_transparent() {
+        Expr::Ident(ident) => Some(ident),
+        Expr::Member(member) => get_member_root_ident(&member.obj),
+        _ => None,
+    }
+}
+
+/// Root object of member assignment: `Menu.Item.Icon = ...` -> `Menu`
+pub fn get_assign_root_ident(target: &AssignTarget) -> Option<&Ident> {
+    match target {
+        AssignTarget::Simple(SimpleAssignTarget::Member(member)) => {
+            get_member_root_ident(&member.obj)
+        }
+        _ => None,
+    }
+}
+
+fn is_ident_named(expr: &Expr, name: &str) -> bool {
+    matches!(expr.unwrap_transparent(), Expr::Ident(ident) if ident.sym.as_str() == name)
+}
+
+fn is_require_call(expr: &Expr) -> bool {
+    match expr.unwrap_transparent() {
+        Expr::Call(CallExpr {
+            callee: Callee::Expr(callee),
+            ..
+        }) => is_ident_named(callee, "require"),
+        // `require("./Button").default`
+        Expr::Member(MemberExpr { obj, .. }) => is_require_call(obj),
+        _ => false,
+    }
+}
+
+/// Source of CommonJS import: `require("react/jsx-runtime")`
+fn get_require_source(expr: &Expr) -> Option<&str> {
+    match expr.unwrap_transparent() {
+        Expr::Call(CallExpr {
+            callee: Callee::Expr(callee),
+            args,
+            ..
+        }) if is_ident_named(callee, "require") => match args.first() {
+            Some(ExprOrSpread { spread: None, expr }) => match expr.unwrap_transparent() {
+                Expr::Lit(Lit::Str(source)) => Some(source.value.as_str()),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Span of CommonJS import statement: `const { signal } = require("@preact/signals-react")`
+pub fn get_require_span(stmt: &Stmt) -> Option<Span> {
+    match stmt {
+        Stmt::Decl(Decl::Var(var_decl))
+            if var_decl
+                .decls
+                .iter()
+                .any(|it| it.init.as_deref().is_some_and(is_require_call)) =>
+        {
+            Some(var_decl.span)
+        }
+        Stmt::Expr(ExprStmt { span, expr }) if is_require_call(expr) => Some(*span),
+        _ => None,
+    }
+}
+
+/// Checks CommonJS default export targets: `module.exports`, `exports.default`
+/// and `module.exports.default`
+pub fn is_commonjs_default_export(target: &AssignTarget) -> bool {
+    let member = match target {
+        AssignTarget::Simple(SimpleAssignTarget::Member(member)) => member,
+        _ => return false,
+    };
+    match (member.obj.unwrap_transparent(), &member.prop) {
+        (obj @ Expr::Ident(_), MemberProp::Ident(prop)) => match prop.sym.as_str() {
+            "exports" => is_ident_named(obj, "module"),
+            "default" => is_ident_named(obj, "exports"),
+            _ => false,
+        },
+        (
+            Expr::Member(MemberExpr {
+                obj,
+                prop: MemberProp::Ident(exports),
+                span: _,
+            }),
+            MemberProp::Ident(prop),
+        ) => {
+            prop.sym.as_str() == "default"
+                && exports.sym.as_str() == "exports"
+                && is_ident_named(obj, "module")
+        }
+        _ => false,
+    }
+}
+
 pub trait Blockable {
     fn to_block(&mut self) -> BlockStmt;
 }
@@ -291,29 +822,724 @@ impl Blockable for BlockStmtOrExpr {
         }
     }
 }
-struct HasJSX {
+pub fn build_glob_set(globs: &[String]) -> GlobSet {
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        builder.add(Glob::new(glob).expect("file patterns should be valid globs"));
+    }
+    builder
+        .build()
+        .expect("file patterns should be valid globs")
+}
+
+/// File name relative to host's working directory with `/` separators, file
+/// globs are matched against it: `src/**` instead of `**/src/**`
+pub fn get_relative_path(filename: &str, cwd: Option<&str>) -> String {
+    let filename = filename.replace('\\', "/");
+    match cwd.map(|it| it.replace('\\', "/")) {
+        Some(cwd)
+            if let Some(relative) = filename
+                .strip_prefix(cwd.trim_end_matches('/'))
+                .and_then(|it| it.strip_prefix('/')) =>
+        {
+            relative.to_owned()
+        }
+        _ => filename,
+    }
+}
+
+/// Files, which are transformed: ones matching any of `include` globs (every
+/// file when not set) and none of `exclude` globs
+pub struct PathFilter {
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+}
+
+impl PathFilter {
+    pub fn new(include: Option<&[String]>, exclude: Option<&[String]>) -> Self {
+        PathFilter {
+            include: include.map(build_glob_set),
+            exclude: exclude.map(build_glob_set),
+        }
+    }
+
+    pub fn allows(&self, path: &str) -> bool {
+        self.include.as_ref().map_or(true, |it| it.is_match(path))
+            && !self.exclude.as_ref().is_some_and(|it| it.is_match(path))
+    }
+}
+
+/// Functions which create elements, used to detect precompiled JSX:
+/// `React.createElement`, `h` and imports from `react/jsx-runtime`
+pub struct JsxPragmas {
+    names: Vec<String>,
+    runtime_idents: HashSet<Id>,
+}
+
+impl JsxPragmas {
+    pub fn new(names: Option<Vec<String>>) -> Self {
+        JsxPragmas {
+            names: names.unwrap_or_else(get_default_jsx_pragmas),
+            runtime_idents: HashSet::new(),
+        }
+    }
+
+    /// Collects local names of `react/jsx-runtime` and `react/jsx-dev-runtime` imports
+    pub fn collect_runtime_imports(&mut self, module: &Module) {
+        self.runtime_idents.clear();
+        for item in module.body.iter() {
+            match item {
+                ModuleItem::ModuleDecl(ModuleDecl::Import(import))
+                    if is_jsx_runtime_source(import.src.value.as_str()) =>
+                {
+                    self.runtime_idents
+                        .extend(import.specifiers.iter().map(|it| match it {
+                            ImportSpecifier::Named(it) => it.local.to_id(),
+                            ImportSpecifier::Default(it) => it.local.to_id(),
+                            ImportSpecifier::Namespace(it) => it.local.to_id(),
+                        }));
+                }
+                ModuleItem::Stmt(stmt) => self.collect_runtime_require(stmt),
+                _ => {}
+            }
+        }
+    }
+
+    /// Collects bindings of `react/jsx-runtime` and `react/jsx-dev-runtime` requires in
+    /// CommonJS script: `const _jsxRuntime = require("react/jsx-runtime")`
+    pub fn collect_script_runtime_imports(&mut self, script: &Script) {
+        self.runtime_idents.clear();
+        for stmt in script.body.iter() {
+            self.collect_runtime_require(stmt);
+        }
+    }
+
+    fn collect_runtime_require(&mut self, stmt: &Stmt) {
+        if let Stmt::Decl(Decl::Var(var_decl)) = stmt {
+            for declarator in var_decl.decls.iter() {
+                if declarator
+                    .init
+                    .as_deref()
+                    .and_then(get_require_source)
+                    .is_some_and(is_jsx_runtime_source)
+                {
+                    self.runtime_idents
+                        .extend(find_pat_ids::<_, Id>(&declarator.name));
+                }
+            }
+        }
+    }
+
+    fn is_runtime_ident(&self, expr: &Expr) -> bool {
+        matches!(expr.unwrap_transparent(), Expr::Ident(ident) if self.runtime_idents.contains(&ident.to_id()))
+    }
+
+    fn is_jsx_call(&self, callee: &Callee) -> bool {
+        let expr = match callee {
+            Callee::Expr(expr) => expr.unwrap_transparent(),
+            _ => return false,
+        };
+        // CommonJS output calls runtime without `this`: `(0, _jsxRuntime.jsx)("div", {})`
+        let expr = match expr {
+            Expr::Seq(SeqExpr { exprs, .. }) => match exprs.last() {
+                Some(expr) => expr.unwrap_transparent(),
+                None => return false,
+            },
+            expr => expr,
+        };
+        match expr {
+            Expr::Member(MemberExpr { obj, .. }) if self.is_runtime_ident(obj) => true,
+            expr if self.is_runtime_ident(expr) => true,
+            expr => get_callee_name(expr)
+                .map(|name| self.names.iter().any(|it| it.as_str() == name.as_str()))
+                .unwrap_or(false),
+        }
+    }
+}
+
+impl Default for JsxPragmas {
+    fn default() -> Self {
+        JsxPragmas::new(None)
+    }
+}
+
+fn get_default_jsx_pragmas() -> Vec<String> {
+    ["React.createElement", "createElement"]
+        .iter()
+        .map(|it| it.to_string())
+        .collect()
+}
+
+fn is_jsx_runtime_source(source: &str) -> bool {
+    source.ends_with("/jsx-runtime") || source.ends_with("/jsx-dev-runtime")
+}
+
+struct HasJSX<'a> {
     found: bool,
+    pragmas: &'a JsxPragmas,
 }
-impl Visit for HasJSX {
+impl Visit for HasJSX<'_> {
     fn visit_jsx_element(&mut self, _: &JSXElement) {
         self.found = true;
     }
     fn visit_jsx_fragment(&mut self, _: &JSXFragment) {
         self.found = true;
     }
+    fn visit_call_expr(&mut self, n: &CallExpr) {
+        if self.found {
+            return;
+        }
+        if self.pragmas.is_jsx_call(&n.callee) {
+            self.found = true;
+            return;
+        }
+        n.visit_children_with(self);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum BindingKind {
+    Signal,
+    NotSignal,
+}
+
+fn get_callee_ident_name(callee: &Callee) -> Option<&str> {
+    match callee {
+        Callee::Expr(expr) => match expr.unwrap_transparent() {
+            Expr::Ident(ident) => Some(ident.sym.as_str()),
+            Expr::Member(MemberExpr {
+                prop: MemberProp::Ident(prop),
+                ..
+            }) => Some(prop.sym.as_str()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn get_init_kind(init: &Expr) -> Option<BindingKind> {
+    match init.unwrap_transparent() {
+        Expr::Call(CallExpr { callee, .. }) => match get_callee_ident_name(callee)? {
+            "signal" | "computed" | "useSignal" | "useComputed" => Some(BindingKind::Signal),
+            "useRef" | "createRef" => Some(BindingKind::NotSignal),
+            _ => None,
+        },
+        Expr::Lit(_)
+        | Expr::Tpl(_)
+        | Expr::Object(_)
+        | Expr::Array(_)
+        | Expr::Arrow(_)
+        | Expr::Fn(_)
+        | Expr::Class(_)
+        | Expr::JSXElement(_)
+        | Expr::JSXFragment(_) => Some(BindingKind::NotSignal),
+        _ => None,
+    }
+}
+
+fn get_type_kind(type_ann: &TsTypeAnn) -> Option<BindingKind> {
+    let name = match type_ann.type_ann.as_ref() {
+        TsType::TsTypeRef(TsTypeRef {
+            type_name: TsEntityName::Ident(ident),
+            ..
+        }) => ident.sym.as_str(),
+        TsType::TsTypeRef(TsTypeRef {
+            type_name: TsEntityName::TsQualifiedName(name),
+            ..
+        }) => name.right.sym.as_str(),
+        _ => return None,
+    };
+    match name {
+        "Signal" | "ReadonlySignal" => Some(BindingKind::Signal),
+        name if name.ends_with("Event") => Some(BindingKind::NotSignal),
+        _ => None,
+    }
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+pub enum SignalModule {
+    /// every named export of matching modules is a signal: `"@app/state/*"`
+    Pattern(String),
+    /// only listed exports are signals: `{ "./store": ["count", "user"] }`
+    Exports(HashMap<String, Vec<String>>),
+}
+
+/// Modules, which named exports are known to be signals. Sources are matched
+/// as written in import: `./store` and `../store` are different modules
+#[derive(Clone, Default)]
+pub struct SignalModules {
+    patterns: Vec<Regex>,
+    exports: HashMap<String, HashSet<String>>,
+}
+
+impl SignalModules {
+    pub fn new(modules: Option<Vec<SignalModule>>) -> Self {
+        let mut patterns = vec![];
+        let mut exports: HashMap<String, HashSet<String>> = HashMap::new();
+        for module in modules.into_iter().flatten() {
+            match module {
+                SignalModule::Pattern(pattern) => patterns.push(get_module_pattern_regex(&pattern)),
+                SignalModule::Exports(modules) => {
+                    for (source, names) in modules {
+                        exports.entry(source).or_default().extend(names);
+                    }
+                }
+            }
+        }
+        SignalModules { patterns, exports }
+    }
+
+    fn is_signal_module(&self, source: &str) -> bool {
+        self.exports.contains_key(source) || self.patterns.iter().any(|it| it.is_match(source))
+    }
+
+    /// Manifest only adds knowledge: exports, which are not listed, stay unknown
+    fn get_export_kind(&self, source: &str, name: &str) -> Option<BindingKind> {
+        let is_listed = self.patterns.iter().any(|it| it.is_match(source))
+            || self
+                .exports
+                .get(source)
+                .is_some_and(|names| names.contains(name));
+        is_listed.then_some(BindingKind::Signal)
+    }
+}
+
+fn get_module_pattern_regex(pattern: &str) -> Regex {
+    let pattern = pattern
+        .split('*')
+        .map(regex::escape)
+        .collect::<Vec<_>>()
+        .join(".*");
+    Regex::new(format!("^{}$", pattern).as_str()).unwrap()
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoDetectOptions {
+    /// properties, which read signals: `["value"]`
+    properties: Option<Vec<String>>,
+    /// properties, which read signals without subscription: `["peek"]`
+    untracked_properties: Option<Vec<String>>,
+    /// functions, which calls read signals: `["$deref", "useSignalValue"]`
+    calls: Option<Vec<String>>,
+}
+
+/// Accessors, which are counted as signal reads by auto mode
+#[derive(Clone)]
+pub struct AutoDetect {
+    properties: HashSet<String>,
+    untracked_properties: HashSet<String>,
+    calls: HashSet<String>,
+}
+
+impl AutoDetect {
+    pub fn new(options: Option<AutoDetectOptions>) -> Self {
+        let options = options.unwrap_or_default();
+        AutoDetect {
+            properties: options
+                .properties
+                .unwrap_or_else(|| vec!["value".to_owned()])
+                .into_iter()
+                .collect(),
+            untracked_properties: options
+                .untracked_properties
+                .unwrap_or_else(|| vec!["peek".to_owned()])
+                .into_iter()
+                .collect(),
+            calls: options.calls.unwrap_or_default().into_iter().collect(),
+        }
+    }
+
+    fn is_tracked_property(&self, name: &str) -> bool {
+        self.properties.contains(name)
+    }
+
+    fn is_untracked_property(&self, name: &str) -> bool {
+        self.untracked_properties.contains(name)
+    }
+
+    fn is_read_call(&self, name: &str) -> bool {
+        self.calls.contains(name)
+    }
+}
+
+impl Default for AutoDetect {
+    fn default() -> Self {
+        AutoDetect::new(None)
+    }
+}
+
+/// Resolved bindings of module, which are known to be signals or known to
+/// be anything else. Bindings are identified by resolver's syntax context
+#[derive(Default)]
+pub struct SignalScope {
+    bindings: HashMap<Id, BindingKind>,
+    unresolved_mark: Option<Mark>,
+    modules: SignalModules,
+    auto_detect: AutoDetect,
+    /// namespace imports of signal modules with their sources
+    namespaces: HashMap<Id, String>,
+    /// local functions which read signals when called, directly or through
+    /// other local functions
+    reading_functions: HashSet<Id>,
+    /// local functions which read `.value` of their parameters, with indexes
+    /// of those parameters
+    signal_params: HashMap<Id, Vec<usize>>,
+}
+
+impl SignalScope {
+    pub fn collect<N>(
+        n: &N,
+        unresolved_mark: Option<Mark>,
+        modules: &SignalModules,
+        auto_detect: &AutoDetect,
+    ) -> Self
+    where
+        N: VisitWith<SignalScope> + for<'a> VisitWith<LocalFunctions<'a>>,
+    {
+        let mut scope = SignalScope {
+            bindings: HashMap::new(),
+            unresolved_mark,
+            modules: modules.clone(),
+            auto_detect: auto_detect.clone(),
+            namespaces: HashMap::new(),
+            reading_functions: HashSet::new(),
+            signal_params: HashMap::new(),
+        };
+        n.visit_with(&mut scope);
+
+        let functions = {
+            let mut collector = LocalFunctions {
+                scope: &scope,
+                functions: vec![],
+            };
+            n.visit_with(&mut collector);
+            collector.functions
+        };
+        scope.signal_params = functions
+            .iter()
+            .filter(|it| !it.param_reads.is_empty())
+            .map(|it| (it.id.clone(), it.param_reads.clone()))
+            .collect();
+        scope.reading_functions = get_reading_functions(&functions, &scope.signal_params);
+
+        scope
+    }
+
+    /// Kind of aliased binding or of `namespace.export`
+    fn get_expr_kind(&self, expr: &Expr) -> Option<BindingKind> {
+        match expr.unwrap_transparent() {
+            Expr::Ident(ident) => self.bindings.get(&ident.to_id()).copied(),
+            Expr::Member(MemberExpr {
+                obj: box Expr::Ident(namespace),
+                prop: MemberProp::Ident(prop),
+                ..
+            }) => {
+                let source = self.namespaces.get(&namespace.to_id())?;
+                self.modules.get_export_kind(source, prop.sym.as_str())
+            }
+            _ => None,
+        }
+    }
+
+    /// const { count, user: currentUser } = store
+    fn insert_namespace_props(&mut self, object: &ObjectPat, init: &Expr) {
+        let Expr::Ident(namespace) = init.unwrap_transparent() else {
+            return;
+        };
+        let Some(source) = self.namespaces.get(&namespace.to_id()) else {
+            return;
+        };
+        for prop in object.props.iter() {
+            let (name, id) = match prop {
+                ObjectPatProp::KeyValue(KeyValuePatProp {
+                    key: PropName::Ident(key),
+                    value: box Pat::Ident(binding),
+                }) => (key.sym.as_str(), binding.id.to_id()),
+                ObjectPatProp::Assign(AssignPatProp { key, .. }) => (key.sym.as_str(), key.to_id()),
+                _ => continue,
+            };
+            if let Some(kind) = self.modules.get_export_kind(source, name) {
+                self.bindings.insert(id, kind);
+            }
+        }
+    }
+
+    fn insert_typed_pat(&mut self, pat: &Pat) {
+        if let Pat::Ident(BindingIdent {
+            id,
+            type_ann: Some(type_ann),
+        }) = pat
+            && let Some(kind) = get_type_kind(type_ann)
+        {
+            self.bindings.insert(id.to_id(), kind);
+        }
+    }
+
+    fn is_non_signal_global(&self, ident: &Ident) -> bool {
+        self.unresolved_mark
+            .map(|mark| ident.span.ctxt.outer() == mark)
+            .unwrap_or(false)
+            && matches!(ident.sym.as_str(), "event" | "window" | "document")
+    }
+
+    /// Unknown receivers are treated as signals
+    pub fn is_maybe_signal(&self, expr: &Expr) -> bool {
+        match expr.unwrap_transparent() {
+            Expr::Ident(ident) => match self.bindings.get(&ident.to_id()) {
+                Some(kind) => *kind == BindingKind::Signal,
+                None => !self.is_non_signal_global(ident),
+            },
+            expr if let Some(kind) = self.get_expr_kind(expr) => kind == BindingKind::Signal,
+            // `event.target.value`, `ref.current.value`
+            Expr::Member(MemberExpr {
+                prop: MemberProp::Ident(prop),
+                ..
+            }) => !matches!(prop.sym.as_str(), "target" | "currentTarget" | "current"),
+            Expr::Lit(_) | Expr::Tpl(_) | Expr::Object(_) | Expr::Array(_) | Expr::This(_) => false,
+            _ => true,
+        }
+    }
+}
+
+impl Visit for SignalScope {
+    fn visit_var_declarator(&mut self, n: &VarDeclarator) {
+        match &n.name {
+            Pat::Ident(binding) => {
+                if let Some(kind) = binding
+                    .type_ann
+                    .as_deref()
+                    .and_then(get_type_kind)
+                    .or_else(|| n.init.as_deref().and_then(get_init_kind))
+                    .or_else(|| n.init.as_deref().and_then(|it| self.get_expr_kind(it)))
+                {
+                    self.bindings.insert(binding.id.to_id(), kind);
+                }
+            }
+            Pat::Object(object) if let Some(init) = n.init.as_deref() => {
+                self.insert_namespace_props(object, init);
+            }
+            _ => {}
+        }
+        n.visit_children_with(self);
+    }
+    fn visit_import_decl(&mut self, n: &ImportDecl) {
+        if n.type_only {
+            return;
+        }
+        let source = n.src.value.as_str();
+        for specifier in n.specifiers.iter() {
+            match specifier {
+                ImportSpecifier::Named(named) => {
+                    let name = match &named.imported {
+                        Some(ModuleExportName::Ident(ident)) => ident.sym.as_str(),
+                        Some(ModuleExportName::Str(name)) => name.value.as_str(),
+                        None => named.local.sym.as_str(),
+                    };
+                    if let Some(kind) = self.modules.get_export_kind(source, name) {
+                        self.bindings.insert(named.local.to_id(), kind);
+                    }
+                }
+                ImportSpecifier::Namespace(namespace) if self.modules.is_signal_module(source) => {
+                    self.namespaces
+                        .insert(namespace.local.to_id(), source.to_owned());
+                }
+                _ => {}
+            }
+        }
+    }
+    fn visit_param(&mut self, n: &Param) {
+        self.insert_typed_pat(&n.pat);
+        n.visit_children_with(self);
+    }
+    fn visit_arrow_expr(&mut self, n: &ArrowExpr) {
+        for pat in n.params.iter() {
+            self.insert_typed_pat(pat);
+        }
+        n.visit_children_with(self);
+    }
+}
+
+/// Locally declared function with its direct signal reads and calls, which
+/// happen during its call
+struct LocalFunction {
+    id: Id,
+    reads: bool,
+    param_reads: Vec<usize>,
+    calls: Vec<LocalCall>,
+}
+
+/// Call of local function with flags of arguments, which may be signals
+struct LocalCall {
+    id: Id,
+    signal_args: Vec<bool>,
+}
+
+pub struct LocalFunctions<'a> {
+    scope: &'a SignalScope,
+    functions: Vec<LocalFunction>,
+}
+
+fn get_param_id(pat: &Pat) -> Option<Id> {
+    match pat {
+        Pat::Ident(binding) => Some(binding.id.to_id()),
+        _ => None,
+    }
+}
+
+impl<'a> LocalFunctions<'a> {
+    fn insert<N>(&mut self, ident: &Ident, n: &N, params: Vec<Option<Id>>)
+    where
+        N: VisitWith<HasDotValue<'a>>,
+    {
+        let v = find_dot_value(n, self.scope, params);
+        self.functions.push(LocalFunction {
+            id: ident.to_id(),
+            reads: v.found,
+            param_reads: v.param_reads,
+            calls: v.calls,
+        });
+    }
+}
+
+impl<'a> Visit for LocalFunctions<'a> {
+    fn visit_fn_decl(&mut self, n: &FnDecl) {
+        let params = n.function.params.iter().map(|it| get_param_id(&it.pat));
+        self.insert(&n.ident, &*n.function, params.collect());
+        n.visit_children_with(self);
+    }
+    fn visit_var_declarator(&mut self, n: &VarDeclarator) {
+        if let Pat::Ident(binding) = &n.name
+            && let Some(init) = &n.init
+        {
+            match init.unwrap_transparent() {
+                Expr::Arrow(arrow_expr) => {
+                    let params = arrow_expr.params.iter().map(get_param_id);
+                    self.insert(&binding.id, arrow_expr, params.collect())
+                }
+                Expr::Fn(fn_expr) => {
+                    let params = fn_expr
+                        .function
+                        .params
+                        .iter()
+                        .map(|it| get_param_id(&it.pat));
+                    self.insert(&binding.id, fn_expr, params.collect())
+                }
+                _ => {}
+            }
+        }
+        n.visit_children_with(self);
+    }
 }
 
-struct HasDotValue {
+fn get_reading_functions(
+    functions: &[LocalFunction],
+    signal_params: &HashMap<Id, Vec<usize>>,
+) -> HashSet<Id> {
+    let mut reading: HashSet<Id> = HashSet::new();
+    loop {
+        let len = reading.len();
+        for function in functions.iter() {
+            if !reading.contains(&function.id)
+                && (function.reads
+                    || function.calls.iter().any(|call| {
+                        reading.contains(&call.id)
+                            || is_signal_passed(signal_params, &call.id, |index| {
+                                call.signal_args.get(index).copied().unwrap_or(false)
+                            })
+                    }))
+            {
+                reading.insert(function.id.clone());
+            }
+        }
+        if reading.len() == len {
+            return reading;
+        }
+    }
+}
+
+/// Whether function reading `.value` of its parameters gets possible signal
+fn is_signal_passed<F>(signal_params: &HashMap<Id, Vec<usize>>, id: &Id, is_signal_arg: F) -> bool
+where
+    F: Fn(usize) -> bool,
+{
+    signal_params
+        .get(id)
+        .map(|indexes| indexes.iter().any(|index| is_signal_arg(*index)))
+        .unwrap_or(false)
+}
+
+fn is_event_handler_name(name: &str) -> bool {
+    static RE: Lazy<Regex> = Lazy::new(|| Regex::new("^on[A-Z]").unwrap());
+    RE.is_match(name)
+}
+
+/// Hooks which call passed functions outside of render phase
+fn is_non_render_hook_name(name: &str) -> bool {
+    matches!(
+        name,
+        "useEffect" | "useLayoutEffect" | "useSignalEffect" | "useCallback"
+    )
+}
+
+fn is_function_expr(expr: &Expr) -> bool {
+    matches!(expr.unwrap_transparent(), Expr::Arrow(_) | Expr::Fn(_))
+}
+
+fn get_prop_name(key: &PropName) -> Option<&str> {
+    match key {
+        PropName::Ident(ident) => Some(ident.sym.as_str()),
+        PropName::Str(str) => Some(str.value.as_str()),
+        _ => None,
+    }
+}
+
+/// `const { value } = count` and `({ value: user }) => ...`, including nested
+/// patterns and defaults
+fn has_tracked_pat(pat: &Pat, auto_detect: &AutoDetect) -> bool {
+    match pat {
+        Pat::Object(object) => object.props.iter().any(|prop| match prop {
+            ObjectPatProp::KeyValue(KeyValuePatProp { key, value }) => {
+                get_prop_name(key).is_some_and(|it| auto_detect.is_tracked_property(it))
+                    || has_tracked_pat(value, auto_detect)
+            }
+            ObjectPatProp::Assign(AssignPatProp { key, .. }) => {
+                auto_detect.is_tracked_property(key.sym.as_str())
+            }
+            ObjectPatProp::Rest(_) => false,
+        }),
+        Pat::Array(array) => array
+            .elems
+            .iter()
+            .flatten()
+            .any(|it| has_tracked_pat(it, auto_detect)),
+        Pat::Assign(AssignPat { left, .. }) => has_tracked_pat(left, auto_detect),
+        _ => false,
+    }
+}
+
+/// Searches for `.value` reads which happen during render, so reads inside
+/// of event handlers and effects are ignored
+pub struct HasDotValue<'a> {
     found: bool,
+    scope: &'a SignalScope,
+    /// parameters of local function, which reads depend on passed arguments
+    params: Vec<Option<Id>>,
+    param_reads: Vec<usize>,
+    /// local functions called during render
+    calls: Vec<LocalCall>,
+    /// depth of functions, parameters are destructured reads only for the visited one
+    function_depth: usize,
 }
-impl Visit for HasDotValue {
+impl Visit for HasDotValue<'_> {
     fn visit_member_expr(&mut self, n: &MemberExpr) {
         if self.found {
             return;
         }
 
-        if match &n.prop {
-            MemberProp::Ident(ident) => ident.sym.as_str() == "value",
+        let prop_name = match &n.prop {
+            MemberProp::Ident(ident) => Some(ident.sym.as_str()),
             MemberProp::Computed(ComputedPropName { span: _, expr }) => {
                 if let Expr::Lit(Lit::Str(Str {
                     span: _,
@@ -321,78 +1547,239 @@ impl Visit for HasDotValue {
                     raw: _,
                 })) = expr.unwrap_parens()
                 {
-                    value.as_str() == "value"
+                    Some(value.as_str())
                 } else {
-                    false
+                    None
                 }
             }
-            _ => false,
-        } {
+            _ => None,
+        };
+        let auto_detect = &self.scope.auto_detect;
+        // `count.peek()` doesn't subscribe to the receiver
+        if prop_name.is_some_and(|it| auto_detect.is_untracked_property(it)) {
+            return;
+        }
+        if prop_name.is_some_and(|it| auto_detect.is_tracked_property(it)) {
+            if let Expr::Ident(ident) = n.obj.unwrap_transparent()
+                && let Some(index) = self
+                    .params
+                    .iter()
+                    .position(|it| it.as_ref() == Some(&ident.to_id()))
+            {
+                if !self.param_reads.contains(&index) {
+                    self.param_reads.push(index);
+                }
+                return;
+            }
+            if self.scope.is_maybe_signal(&n.obj) {
+                self.found = true;
+                return;
+            }
+        }
+        n.visit_children_with(self);
+    }
+    fn visit_var_declarator(&mut self, n: &VarDeclarator) {
+        if let Some(init) = &n.init
+            && has_tracked_pat(&n.name, &self.scope.auto_detect)
+            && self.scope.is_maybe_signal(init)
+        {
+            self.found = true;
+            return;
+        }
+        // `const onClick = () => count.value++` reads only when called, calls of
+        // local functions are checked with `reading_functions`
+        if let Pat::Ident(_) = &n.name
+            && let Some(init) = &n.init
+            && is_function_expr(init)
+        {
+            return;
+        }
+        n.visit_children_with(self);
+    }
+    fn visit_fn_decl(&mut self, _: &FnDecl) {}
+    fn visit_function(&mut self, n: &Function) {
+        self.function_depth += 1;
+        n.visit_children_with(self);
+        self.function_depth -= 1;
+    }
+    fn visit_param(&mut self, n: &Param) {
+        // `options.map(({ value }) => <option value={value} />)` doesn't read signals
+        if self.function_depth == 1 && has_tracked_pat(&n.pat, &self.scope.auto_detect) {
+            self.found = true;
+            return;
+        }
+        n.visit_children_with(self);
+    }
+    fn visit_arrow_expr(&mut self, n: &ArrowExpr) {
+        if self.function_depth == 0
+            && n.params
+                .iter()
+                .any(|it| has_tracked_pat(it, &self.scope.auto_detect))
+        {
+            self.found = true;
+            return;
+        }
+        self.function_depth += 1;
+        n.visit_children_with(self);
+        self.function_depth -= 1;
+    }
+    fn visit_jsx_attr(&mut self, n: &JSXAttr) {
+        if let JSXAttrName::Ident(name) = &n.name
+            && is_event_handler_name(name.sym.as_str())
+            && let Some(JSXAttrValue::JSXExprContainer(JSXExprContainer {
+                expr: JSXExpr::Expr(expr),
+                ..
+            })) = &n.value
+            && is_function_expr(expr)
+        {
+            return;
+        }
+        n.visit_children_with(self);
+    }
+    fn visit_call_expr(&mut self, n: &CallExpr) {
+        if self.found {
+            return;
+        }
+        if let Some(name) = get_callee_ident_name(&n.callee)
+            && is_non_render_hook_name(name)
+        {
+            // dependencies are read during render
+            n.callee.visit_with(self);
+            for arg in n.args.iter().filter(|it| !is_function_expr(&it.expr)) {
+                arg.visit_with(self);
+            }
+            return;
+        }
+        if let Callee::Expr(callee) = &n.callee
+            && let Some(name) = get_callee_name(callee)
+            && self.scope.auto_detect.is_read_call(name.as_str())
+        {
             self.found = true;
             return;
         }
+        if let Callee::Expr(callee) = &n.callee
+            && let Expr::Ident(ident) = callee.unwrap_transparent()
+        {
+            let id = ident.to_id();
+            let is_signal_arg = |index: usize| {
+                n.args
+                    .get(index)
+                    .map(|arg| arg.spread.is_none() && self.scope.is_maybe_signal(&arg.expr))
+                    .unwrap_or(false)
+            };
+            if self.scope.reading_functions.contains(&id)
+                || is_signal_passed(&self.scope.signal_params, &id, is_signal_arg)
+            {
+                self.found = true;
+                return;
+            }
+            let signal_args = (0..n.args.len()).map(is_signal_arg).collect();
+            self.calls.push(LocalCall { id, signal_args });
+        }
         n.visit_children_with(self);
     }
 }
 
-fn has_jsx<N>(n: &N) -> bool
+fn has_jsx<'a, N>(n: &N, pragmas: &'a JsxPragmas) -> bool
 where
-    N: VisitWith<HasJSX>,
+    N: VisitWith<HasJSX<'a>>,
 {
-    let mut v = HasJSX { found: false };
+    let mut v = HasJSX {
+        found: false,
+        pragmas,
+    };
     n.visit_children_with(&mut v);
     v.found
 }
-fn has_dot_value<N>(n: &N) -> bool
+fn find_dot_value<'a, N>(n: &N, scope: &'a SignalScope, params: Vec<Option<Id>>) -> HasDotValue<'a>
 where
-    N: VisitWith<HasDotValue>,
+    N: VisitWith<HasDotValue<'a>>,
 {
-    let mut v = HasDotValue { found: false };
-    n.visit_children_with(&mut v);
-    v.found
+    let mut v = HasDotValue {
+        found: false,
+        scope,
+        params,
+        param_reads: vec![],
+        calls: vec![],
+        function_depth: 0,
+    };
+    n.visit_with(&mut v);
+    v
+}
+fn has_dot_value<'a, N>(n: &N, scope: &'a SignalScope) -> bool
+where
+    N: VisitWith<HasDotValue<'a>>,
+{
+    find_dot_value(n, scope, vec![]).found
+}
+
+fn get_block_directives(block: Option<&BlockStmt>) -> Vec<&str> {
+    block
+        .map(|it| it.stmts.iter().map_while(get_directive).collect())
+        .unwrap_or_default()
 }
 
 pub trait Detectable {
-    fn has_jsx(&self) -> bool;
-    fn has_dot_value(&self) -> bool;
+    fn has_jsx(&self, pragmas: &JsxPragmas) -> bool;
+    fn has_dot_value(&self, scope: &SignalScope) -> bool;
+    /// directive prologue of function body: `"use no signals"`
+    fn get_directives(&self) -> Vec<&str>;
 }
 
 impl Detectable for FunctionLike<'_> {
-    fn has_jsx(&self) -> bool {
+    fn has_jsx(&self, pragmas: &JsxPragmas) -> bool {
         match self {
-            FunctionLike::Arrow(arrow_expr) => has_jsx(*arrow_expr),
-            FunctionLike::Fn(fn_expr) => has_jsx(*fn_expr),
+            FunctionLike::Arrow(arrow_expr) => has_jsx(*arrow_expr, pragmas),
+            FunctionLike::Fn(fn_expr) => has_jsx(*fn_expr, pragmas),
         }
     }
-    fn has_dot_value(&self) -> bool {
+    fn has_dot_value(&self, scope: &SignalScope) -> bool {
         match self {
-            FunctionLike::Arrow(arrow_expr) => has_dot_value(*arrow_expr),
-            FunctionLike::Fn(fn_expr) => has_dot_value(*fn_expr),
+            FunctionLike::Arrow(arrow_expr) => has_dot_value(*arrow_expr, scope),
+            FunctionLike::Fn(fn_expr) => has_dot_value(*fn_expr, scope),
+        }
+    }
+    fn get_directives(&self) -> Vec<&str> {
+        match self {
+            FunctionLike::Arrow(arrow_expr) => match arrow_expr.body.as_ref() {
+                BlockStmtOrExpr::BlockStmt(block) => get_block_directives(Some(block)),
+                _ => vec![],
+            },
+            FunctionLike::Fn(fn_expr) => fn_expr.get_directives(),
         }
     }
 }
 impl Detectable for FnDecl {
-    fn has_jsx(&self) -> bool {
-        has_jsx(&self.function)
+    fn has_jsx(&self, pragmas: &JsxPragmas) -> bool {
+        has_jsx(&self.function, pragmas)
     }
-    fn has_dot_value(&self) -> bool {
-        has_dot_value(&self.function)
+    fn has_dot_value(&self, scope: &SignalScope) -> bool {
+        has_dot_value(&self.function, scope)
+    }
+    fn get_directives(&self) -> Vec<&str> {
+        self.function.get_directives()
     }
 }
 impl Detectable for FnExpr {
-    fn has_jsx(&self) -> bool {
-        has_jsx(&self.function)
+    fn has_jsx(&self, pragmas: &JsxPragmas) -> bool {
+        has_jsx(&self.function, pragmas)
+    }
+    fn has_dot_value(&self, scope: &SignalScope) -> bool {
+        has_dot_value(&self.function, scope)
     }
-    fn has_dot_value(&self) -> bool {
-        has_dot_value(&self.function)
+    fn get_directives(&self) -> Vec<&str> {
+        self.function.get_directives()
     }
 }
 impl Detectable for Function {
-    fn has_jsx(&self) -> bool {
-        has_jsx(self)
+    fn has_jsx(&self, pragmas: &JsxPragmas) -> bool {
+        has_jsx(self, pragmas)
+    }
+    fn has_dot_value(&self, scope: &SignalScope) -> bool {
+        has_dot_value(self, scope)
     }
-    fn has_dot_value(&self) -> bool {
-        has_dot_value(self)
+    fn get_directives(&self) -> Vec<&str> {
+        get_block_directives(self.body.as_ref())
     }
 }
 
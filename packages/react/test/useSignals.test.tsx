@@ -1,6 +1,10 @@
 import React, { Fragment } from "react";
 import { Signal, batch, signal } from "@preact/signals-core";
-import { useSignals } from "../src/lib/tracking";
+import {
+  trackClassSignals,
+  useHookSignals,
+  useSignals,
+} from "../src/lib/tracking";
 import { sleep, tryit } from "radash";
 import {
   describe,
@@ -561,4 +565,213 @@ describe("useSignals", () => {
     await render(<App />);
     expect(scratch.innerHTML).to.equal("11");
   });
+
+  describe("trackClassSignals", () => {
+    it("should rerender class components when signals they use change", async () => {
+      const sig = signal(0);
+      class Counter extends React.Component {
+        render() {
+          const store = trackClassSignals(this);
+          try {
+            return <p>{sig.value}</p>;
+          } finally {
+            store.f();
+          }
+        }
+      }
+
+      await render(<Counter />);
+      expect(scratch.innerHTML).to.equal("<p>0</p>");
+
+      await act(() => {
+        sig.value += 1;
+      });
+      expect(scratch.innerHTML).to.equal("<p>1</p>");
+    });
+
+    it("should stop tracking and call original componentWillUnmount on unmount", async () => {
+      const sig = signal(0);
+      const willUnmount = vi.fn();
+      let renders = 0;
+      class Counter extends React.Component {
+        componentWillUnmount() {
+          willUnmount();
+        }
+        render() {
+          const store = trackClassSignals(this);
+          try {
+            renders++;
+            return <p>{sig.value}</p>;
+          } finally {
+            store.f();
+          }
+        }
+      }
+
+      await render(<Counter />);
+      await render(null);
+      expect(willUnmount).toHaveBeenCalledOnce();
+
+      const rendersBeforeUpdate = renders;
+      await act(() => {
+        sig.value += 1;
+      });
+      expect(renders).to.equal(rendersBeforeUpdate);
+    });
+
+    it("should keep tracking after StrictMode remount", async () => {
+      const sig = signal(0);
+      class Counter extends React.Component {
+        render() {
+          const store = trackClassSignals(this);
+          try {
+            return <p>{sig.value}</p>;
+          } finally {
+            store.f();
+          }
+        }
+      }
+
+      await render(
+        <React.StrictMode>
+          <Counter />
+        </React.StrictMode>
+      );
+      expect(scratch.innerHTML).to.equal("<p>0</p>");
+
+      await act(() => {
+        sig.value += 1;
+      });
+      expect(scratch.innerHTML).to.equal("<p>1</p>");
+    });
+
+    it("should not subscribe instances, which are rendered but never mounted", () => {
+      const sig = signal(0);
+      class Counter extends React.Component {
+        render() {
+          const store = trackClassSignals(this);
+          try {
+            return <p>{sig.value}</p>;
+          } finally {
+            store.f();
+          }
+        }
+      }
+
+      const instance = new Counter({});
+      const forceUpdate = vi.spyOn(instance, "forceUpdate");
+      instance.render();
+      sig.value += 1;
+      expect(forceUpdate).not.toHaveBeenCalled();
+    });
+
+    it("should rerender on mount when signals changed after render", () => {
+      const sig = signal(0);
+      class Counter extends React.Component {
+        render() {
+          const store = trackClassSignals(this);
+          try {
+            return <p>{sig.value}</p>;
+          } finally {
+            store.f();
+          }
+        }
+      }
+
+      const instance = new Counter({});
+      const forceUpdate = vi
+        .spyOn(instance, "forceUpdate")
+        .mockImplementation(() => {});
+      instance.render();
+      sig.value += 1;
+      instance.componentDidMount!();
+      expect(forceUpdate).toHaveBeenCalledOnce();
+
+      sig.value += 1;
+      expect(forceUpdate).toHaveBeenCalledTimes(2);
+      instance.componentWillUnmount!();
+    });
+  });
+
+  describe("useHookSignals", () => {
+    it("should rerender untracked component when signals read by hook change", async () => {
+      const sig = signal(0);
+      const useValue = () => {
+        useHookSignals();
+        return sig.value;
+      };
+      function Counter() {
+        return <p>{useValue()}</p>;
+      }
+
+      await render(<Counter />);
+      expect(scratch.innerHTML).to.equal("<p>0</p>");
+
+      await act(() => {
+        sig.value += 1;
+      });
+      expect(scratch.innerHTML).to.equal("<p>1</p>");
+    });
+
+    it("should not track signals of next component rendered in same pass", async () => {
+      const hookSig = signal(0);
+      const siblingSig = signal(0);
+      let hookRenders = 0;
+      const useValue = () => {
+        useHookSignals();
+        return hookSig.value;
+      };
+      function WithHook() {
+        hookRenders++;
+        return <p>{useValue()}</p>;
+      }
+      function Sibling() {
+        const store = useSignals();
+        try {
+          return <p>{siblingSig.value}</p>;
+        } finally {
+          store.f();
+        }
+      }
+
+      await render(
+        <>
+          <WithHook />
+          <Sibling />
+        </>
+      );
+      expect(scratch.innerHTML).to.equal("<p>0</p><p>0</p>");
+
+      const rendersBeforeUpdate = hookRenders;
+      await act(() => {
+        siblingSig.value += 1;
+      });
+      expect(scratch.innerHTML).to.equal("<p>0</p><p>1</p>");
+      expect(hookRenders).to.equal(rendersBeforeUpdate);
+    });
+
+    it("should leave tracking to tracked component", async () => {
+      const sig = signal(0);
+      const useValue = () => {
+        useHookSignals();
+        return sig.value;
+      };
+      function Counter() {
+        const store = useSignals();
+        try {
+          return <p>{useValue()}</p>;
+        } finally {
+          store.f();
+        }
+      }
+
+      await render(<Counter />);
+      expect(scratch.innerHTML).to.equal("<p>0</p>");
+
+      await act(() => {
+        sig.value += 1;
+      });
+      expect(scratch.innerHTML).to.equal("<p>1</p>");
+    });
+  });
 });